@@ -0,0 +1,63 @@
+//! Samples pieces from a randomizer and reports how fair it is.
+//!
+//! ```text
+//! randomizer-stats [--randomizer NAME] [--seed N] [--pieces N] [--format text|csv|json] [--output FILE]
+//! ```
+//!
+//! NAME is one of the randomizers as shown in the settings (RandomGenerator, Random, Bag14, Bag7Plus1, TGM1, TGM3, NES).
+
+use std::process::ExitCode;
+
+use tetris_engine::rng;
+use tetris_engine::stats::RandomizerStats;
+use tetris_engine::Randomizers;
+
+const USAGE: &str = "usage: randomizer-stats [--randomizer NAME] [--seed N] [--pieces N] [--format text|csv|json] [--output FILE]";
+
+fn main() -> ExitCode{
+    match run(std::env::args().skip(1).collect()){
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<(), String>{
+    let mut randomizer = Randomizers::RandomGenerator;
+    let mut seed = rng::random_seed();
+    let mut pieces = 1_000_000;
+    let mut format = String::from("text");
+    let mut output = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next(){
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str(){
+            "--randomizer" => randomizer=value()?.parse()?,
+            "--seed" => seed=value()?.parse().map_err(|_| "the seed must be a number")?,
+            "--pieces" => pieces=value()?.parse().map_err(|_| "the piece count must be a number")?,
+            "--format" => format=value()?,
+            "--output" => output=Some(value()?),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return Ok(())
+            }
+            _ => return Err(format!("unknown argument {}", arg))
+        }
+    }
+    let stats = RandomizerStats::sample(&randomizer, seed, pieces);
+    let report = match format.as_str(){
+        "text" => stats.to_text(),
+        "csv" => stats.to_csv(),
+        "json" => stats.to_json()+"\n",
+        _ => return Err(format!("unknown format {}", format))
+    };
+    match output{
+        Some(path) => std::fs::write(&path, report).map_err(|e| format!("couldn't write {}: {}", path, e)),
+        None => {
+            print!("{}", report);
+            Ok(())
+        }
+    }
+}
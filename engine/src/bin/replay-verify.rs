@@ -0,0 +1,52 @@
+//! Plays replays through the engine and checks the score, lines and time they claim, so that tampered
+//! submissions can be turned away.
+//!
+//! ```text
+//! replay-verify FILE...
+//! ```
+//!
+//! Files can be in either replay encoding (see `tetris_engine::replay`). Prints a line for each and fails if any of them do.
+//! A replay also fails if its game is still going when it ends, since a run cut short could leave out how it went wrong.
+
+use std::process::ExitCode;
+
+use tetris_engine::Replay;
+
+const USAGE: &str = "usage: replay-verify FILE...";
+
+fn main() -> ExitCode{
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() || paths.iter().any(|p| p=="--help" || p=="-h"){
+        eprintln!("{}", USAGE);
+        return if paths.is_empty() {ExitCode::FAILURE} else {ExitCode::SUCCESS}
+    }
+    let mut all_good = true;
+    for path in paths{
+        match verify(&path){
+            Ok(summary) => println!("{}: ok, {}", path, summary),
+            Err(e) => {
+                println!("{}: FAILED, {}", path, e);
+                all_good = false;
+            }
+        }
+    }
+    if all_good {ExitCode::SUCCESS} else {ExitCode::FAILURE}
+}
+
+/// Reads and checks one replay, describing it if it holds up.
+fn verify(path: &str) -> Result<String, String>{
+    let bytes = std::fs::read(path).map_err(|e| format!("couldn't read it: {}", e))?;
+    let replay = Replay::decode(&bytes)?;
+    let game = replay.verify()?;
+    if !game.is_over(){
+        return Err(String::from("the game was still going when the replay ends"))
+    }
+    let end = if game.is_finished() {"finished"} else {"topped out"};
+    Ok(format!("{} with seed {}, score {}, {} lines in {}, {} (engine {})", replay.settings.mode, game.seed(), game.score(), game.lines_cleared(),
+        format_time(game.elapsed_ms()), end, replay.engine_version))
+}
+
+/// A time in milliseconds as minutes, seconds and milliseconds.
+fn format_time(ms: u64) -> String{
+    format!("{}:{:02}.{:03}", ms/60000, ms/1000%60, ms%1000)
+}
@@ -0,0 +1,360 @@
+use crate::piece::TetrisPieceType;
+use crate::rotation::{Kicks180, RotationSystem, RotationSystems};
+use crate::scoring::TSpin;
+
+/// The playfield: locked tiles plus the currently falling piece.
+///
+/// Tiles are stored row by row with row 0 at the bottom. Extra rows above the visible
+/// height act as the spawn buffer. Garbage is kept apart from the tiles pieces left behind,
+/// but blocks pieces and fills rows all the same.
+#[derive(Clone)]
+pub struct TetrisBoard{
+    tiles: Vec<Option<TetrisPieceType>>,
+    garbage: Vec<bool>,
+    /// Cleared rows that had garbage in them.
+    garbage_cleared: u32,
+    dimentions: (isize, isize),
+    visible_height: isize,
+    falling_piece: TetrisPieceType,
+    /// Whether there is a falling piece at all. There is none from when one locks until the next one spawns.
+    falling: bool,
+    /// Column and row of the bottom left corner of the falling piece's 4x4 box.
+    falling_pos: (isize, isize),
+    falling_rot: usize,
+    /// Row the falling piece's box would land on if hard dropped.
+    drop_row: isize,
+    rotation: RotationSystems,
+    /// Whether the last successful rotation was an SRS quarter turn that needed the fifth kick test.
+    fifth_kick: bool
+}
+
+impl TetrisBoard{
+    /// A `width` by `height` SRS board with a three row spawn buffer.
+    pub fn make(width: usize, height: usize, first_falling_piece: TetrisPieceType) -> Self{
+        Self::new(width, height, 3, first_falling_piece, RotationSystems::Srs)
+    }
+    /// A board `width` columns wide (at least 4) with `height` visible rows and `buffer_height` hidden rows above them.
+    /// Pieces spawn in the buffer, so it should be at least 2 rows for them to spawn out of sight.
+    pub fn new(width: usize, height: usize, buffer_height: usize, first_falling_piece: TetrisPieceType, rotation: RotationSystems) -> Self{
+        let tiles = vec![None;width*(height+buffer_height)];
+        let garbage = vec![false;tiles.len()];
+        let mut board = Self{tiles, garbage, garbage_cleared: 0, dimentions: (width as isize, (height+buffer_height) as isize), visible_height: height as isize, falling_piece:first_falling_piece, falling: true,
+        falling_pos: (0, 0), falling_rot:0, drop_row: 0, rotation, fifth_kick: false};
+        board.falling_pos=board.spawn_pos(first_falling_piece);
+        board.update_drop_loc();
+        board
+    }
+
+    /// Tiles left behind by locked pieces. See [`TetrisBoard::is_garbage`] for the rest.
+    pub fn tiles(&self) -> &[Option<TetrisPieceType>]{
+        &self.tiles
+    }
+    pub fn is_garbage(&self, col: isize, row: isize) -> bool{
+        self.garbage[(row*self.dimentions.0+col) as usize]
+    }
+    /// Number of rows with any garbage in them.
+    pub fn garbage_rows(&self) -> u32{
+        self.garbage.chunks(self.dimentions.0 as usize).filter(|row| row.contains(&true)).count() as u32
+    }
+    /// Number of cleared rows that had garbage in them.
+    pub fn garbage_cleared(&self) -> u32{
+        self.garbage_cleared
+    }
+    /// Width and total height (including the spawn buffer) of the board.
+    pub fn dimentions(&self) -> (isize, isize){
+        self.dimentions
+    }
+    /// Number of rows below the spawn buffer.
+    pub fn visible_height(&self) -> isize{
+        self.visible_height
+    }
+    pub fn has_falling_piece(&self) -> bool{
+        self.falling
+    }
+    pub fn falling_piece(&self) -> TetrisPieceType{
+        self.falling_piece
+    }
+    pub fn falling_rot(&self) -> usize{
+        self.falling_rot
+    }
+    pub fn rotation_system(&self) -> &RotationSystems{
+        &self.rotation
+    }
+    /// Column and row of every cell of the falling piece.
+    pub fn falling_cells(&self) -> [(isize,isize);4]{
+        let (x,y) = self.falling_pos;
+        self.rotation.cells(self.falling_piece, self.falling_rot).map(|(c,r)| (x+c, y+r))
+    }
+    /// Overwrites a single locked tile. Useful for setting up positions.
+    pub fn set_tile(&mut self, col: isize, row: isize, tile: Option<TetrisPieceType>){
+        let idx = (row*self.dimentions.0+col) as usize;
+        self.tiles[idx]=tile;
+        self.garbage[idx]=false;
+    }
+    /// Pushes garbage rows in from the bottom, moving everything else up. Each entry of `holes` is the one empty
+    /// column of a row, bottom row first. The falling piece is pushed up too if it would overlap.
+    /// Returns false if anything was pushed out the top of the buffer (top out).
+    pub fn insert_garbage(&mut self, holes: &[isize]) -> bool{
+        let w = self.dimentions.0 as usize;
+        let n = (holes.len()*w).min(self.tiles.len());
+        let overflowed = self.tiles[self.tiles.len()-n..].iter().zip(&self.garbage[self.tiles.len()-n..]).any(|(t,g)| t.is_some() || *g);
+        self.tiles.rotate_right(n);
+        self.garbage.rotate_right(n);
+        for (i,hole) in holes.iter().rev().take(n/w).enumerate(){
+            let row = n/w-1-i;
+            for c in 0..w{
+                self.tiles[row*w+c]=None;
+                self.garbage[row*w+c]=c as isize!=*hole;
+            }
+        }
+        while self.falling && !self.fits(self.falling_rot, 0, 0) && self.falling_pos.1<self.dimentions.1{
+            self.falling_pos.1+=1;
+        }
+        let piece_fits = !self.falling || self.fits(self.falling_rot, 0, 0);
+        self.update_drop_loc();
+        !overflowed && piece_fits
+    }
+    /// Replaces the falling piece with `piece` in rotation `rot`, with the bottom left corner of its 4x4 box at (`col`, `row`).
+    /// Returns false and leaves the board untouched if it does not fit there.
+    pub fn place_falling_piece(&mut self, piece: TetrisPieceType, rot: usize, col: isize, row: isize) -> bool{
+        if !self.fits_at(piece, rot%4, col, row){
+            return false
+        }
+        self.falling_piece=piece;
+        self.falling_rot=rot%4;
+        self.falling_pos=(col, row);
+        self.falling=true;
+        self.update_drop_loc();
+        true
+    }
+
+    /// Whether the falling piece covers the tile at (`col`, `row`).
+    pub fn check_loc_for_falling_piece(&self, col: isize, row: isize) -> bool{
+        let (x,y) = self.falling_pos;
+        self.falling && self.rotation.cells(self.falling_piece, self.falling_rot).contains(&(col-x, row-y))
+    }
+    /// Whether the falling piece's hard drop shadow covers the tile at (`col`, `row`).
+    pub fn check_drop_loc(&self, col: isize, row: isize) -> bool{
+        let x = self.falling_pos.0;
+        self.falling && self.rotation.cells(self.falling_piece, self.falling_rot).contains(&(col-x, row-self.drop_row))
+    }
+    pub fn move_down(&mut self) -> bool{
+        if !self.fits(self.falling_rot, 0, -1){
+            return false
+        }
+        self.falling_pos.1-=1;
+        true
+    }
+    /// Moves the falling piece one column right. Returns false if it was blocked.
+    pub fn move_right(&mut self) -> bool{
+        if !self.fits(self.falling_rot, 1, 0){
+            return false
+        }
+        self.falling_pos.0+=1;
+        self.update_drop_loc();
+        true
+    }
+    /// Moves the falling piece one column left. Returns false if it was blocked.
+    pub fn move_left(&mut self) -> bool{
+        if !self.fits(self.falling_rot, -1, 0){
+            return false
+        }
+        self.falling_pos.0-=1;
+        self.update_drop_loc();
+        true
+    }
+    /// Rows that are completely filled, bottom first. They stay on the board until [`TetrisBoard::clear_lines`].
+    pub fn full_rows(&self) -> Vec<isize>{
+        (0..self.dimentions.1).filter(|r| (0..self.dimentions.0).all(|c| !self.is_free(c, *r))).collect()
+    }
+    /// Whether clearing the full rows would leave the board empty.
+    pub fn clears_everything(&self) -> bool{
+        (0..self.dimentions.1).all(|r| {
+            let filled = (0..self.dimentions.0).filter(|c| !self.is_free(*c, r)).count() as isize;
+            filled==0 || filled==self.dimentions.0
+        })
+    }
+    pub fn clear_lines(&mut self) -> u32{
+        let mut line_counter = 0;
+        for r in 0..self.dimentions.1{
+            let mut filled: bool = true;
+            for c in 0..self.dimentions.0{
+                if self.is_free(c, r){
+                    filled=false;
+                }
+            }
+            if filled{
+                line_counter+=1;
+                if (0..self.dimentions.0).any(|c| self.is_garbage(c, r)){
+                    self.garbage_cleared+=1;
+                }
+                for c in 0..self.dimentions.0{
+                    self.tiles[(r*self.dimentions.0+c) as usize]=None;
+                    self.garbage[(r*self.dimentions.0+c) as usize]=false;
+                }
+            }else{
+                for c in 0..self.dimentions.0{
+                    self.tiles[((r-line_counter)*self.dimentions.0+c) as usize]=self.tiles[(r*self.dimentions.0+c) as usize];
+                    self.garbage[((r-line_counter)*self.dimentions.0+c) as usize]=self.garbage[(r*self.dimentions.0+c) as usize];
+                }
+            }
+        }
+        line_counter as u32
+    }
+    /// Empties the highest row that has any locked tiles, leaving the rows below alone.
+    /// Returns false if the board was already empty.
+    pub fn remove_top_row(&mut self) -> bool{
+        let w = self.dimentions.0 as usize;
+        let filled = |row: usize| self.tiles[row*w..(row+1)*w].iter().any(|t| t.is_some()) || self.garbage[row*w..(row+1)*w].contains(&true);
+        match (0..self.tiles.len()/w).rev().find(|r| filled(*r)){
+            Some(row) => {
+                self.tiles[row*w..(row+1)*w].fill(None);
+                self.garbage[row*w..(row+1)*w].fill(false);
+                true
+            }
+            None => false
+        }
+    }
+    /// Whether there are no locked tiles left.
+    pub fn is_empty(&self) -> bool{
+        self.tiles.iter().all(|t| t.is_none()) && !self.garbage.contains(&true)
+    }
+    /// Whether the falling piece is resting on the floor or a locked tile.
+    pub fn is_grounded(&self) -> bool{
+        !self.fits(self.falling_rot, 0, -1)
+    }
+    /// Recomputes and returns the row the falling piece's box would land on.
+    pub fn update_drop_loc(&mut self) -> isize{
+        let mut i = 0;
+        while self.fits(self.falling_rot, 0, -(i+1)){
+            i+=1;
+        }
+        self.drop_row=self.falling_pos.1-i;
+        self.drop_row
+    }
+    /// Rotates the falling piece clockwise, trying wall kicks if needed. Returns false if every test failed.
+    pub fn rotate_clockwise(&mut self) -> bool{
+        let kicks = self.rotation.kicks(self.falling_piece, self.falling_rot, (self.falling_rot+1)%4, &self.box_blocked());
+        self.rotate_with_kicks(1, &kicks)
+    }
+    /// Rotates the falling piece counter-clockwise, trying wall kicks if needed. Returns false if every test failed.
+    pub fn rotate_counter_clockwise(&mut self) -> bool{
+        let kicks = self.rotation.kicks(self.falling_piece, self.falling_rot, (self.falling_rot+3)%4, &self.box_blocked());
+        self.rotate_with_kicks(3, &kicks)
+    }
+    /// Rotates the falling piece a half turn, using the given kick table if the rotation system has none of its own.
+    /// Returns false if every test failed.
+    pub fn rotate_180(&mut self, kick_table: Kicks180) -> bool{
+        let kicks = self.rotation.half_turn_kicks(self.falling_piece, self.falling_rot, kick_table, &self.box_blocked());
+        self.rotate_with_kicks(2, &kicks)
+    }
+    /// Turns the piece `turns` quarter turns clockwise and takes the first kick offset that fits.
+    fn rotate_with_kicks(&mut self, turns: usize, kicks: &[(isize,isize)]) -> bool{
+        let to = (self.falling_rot+turns)%4;
+        for (i,(x,y)) in kicks.iter().enumerate(){
+            if self.fits(to, *x, *y){
+                // the TST and fin kicks are the fifth test of the SRS quarter turn tables; other tables have no such kick
+                self.fifth_kick=i==4 && turns!=2 && matches!(self.rotation, RotationSystems::Srs);
+                self.falling_rot=to;
+                self.falling_pos=(self.falling_pos.0+x, self.falling_pos.1+y);
+                self.update_drop_loc();
+                return true
+            }
+        }
+        false
+    }
+    /// Whether the falling piece would be a T-spin if it locked where it is, assuming it got there by rotating.
+    ///
+    /// Uses the 3-corner rule: at least three of the cells diagonal to the T's center must be walls or locked tiles.
+    /// It is a full T-spin when both corners on the side the T points at are filled, or when the rotation needed
+    /// the fifth kick test of an SRS quarter turn (the TST and fin kicks); otherwise it is a mini.
+    pub fn t_spin(&self) -> Option<TSpin>{
+        if self.falling_piece!=TetrisPieceType::T{
+            return None
+        }
+        let cells = self.falling_cells();
+        let adjacent = |a: (isize,isize), b: (isize,isize)| (a.0-b.0).abs()+(a.1-b.1).abs()==1;
+        let center = *cells.iter().find(|c| cells.iter().filter(|o| adjacent(**c, **o)).count()==3)?;
+        let nub = *cells.iter().find(|c| adjacent(**c, center) && !cells.contains(&(2*center.0-c.0, 2*center.1-c.1)))?;
+        // the direction the T points and the one perpendicular to it
+        let (dx,dy) = (nub.0-center.0, nub.1-center.1);
+        let (px,py) = (-dy, dx);
+        let filled = |x: isize, y: isize| !self.is_free(center.0+x, center.1+y) as usize;
+        let front = filled(dx+px, dy+py)+filled(dx-px, dy-py);
+        let back = filled(-dx+px, -dy+py)+filled(-dx-px, -dy-py);
+        if front+back<3{
+            None
+        }else if front==2 || self.fifth_kick{
+            Some(TSpin::Full)
+        }else{
+            Some(TSpin::Mini)
+        }
+    }
+    /// Whether the falling piece would fit in rotation `rot` after moving `dx` columns right and `dy` rows up.
+    /// Never true while there is no falling piece.
+    fn fits(&self, rot: usize, dx: isize, dy: isize) -> bool{
+        let (x,y) = self.falling_pos;
+        self.falling && self.fits_at(self.falling_piece, rot, x+dx, y+dy)
+    }
+    /// Whether `piece` in rotation `rot` with its box at (`col`, `row`) is inside the board and clear of locked tiles.
+    fn fits_at(&self, piece: TetrisPieceType, rot: usize, col: isize, row: isize) -> bool{
+        self.rotation.cells(piece, rot).iter().all(|(c,r)| self.is_free(col+c, row+r))
+    }
+    fn is_free(&self, col: isize, row: isize) -> bool{
+        col>=0 && col<self.dimentions.0 && row>=0 && row<self.dimentions.1 && self.tiles[(row*self.dimentions.0+col) as usize].is_none()
+            && !self.garbage[(row*self.dimentions.0+col) as usize]
+    }
+    /// Whether each cell of the falling piece's 4x4 box is off the board or taken, for kicks that depend on the surroundings.
+    fn box_blocked(&self) -> impl Fn(isize, isize) -> bool + '_{
+        let (x,y) = self.falling_pos;
+        move |c,r| !self.is_free(x+c, y+r)
+    }
+    /// Where `piece` spawns: horizontally centered (left of center when it can't be exact), with its lowest cells in
+    /// the first row above the visible field, or as high as a short buffer allows.
+    fn spawn_pos(&self, piece: TetrisPieceType) -> (isize,isize){
+        let cells = self.rotation.cells(piece, 0);
+        let (left, right) = (cells.iter().map(|c| c.0).min().unwrap_or(0), cells.iter().map(|c| c.0).max().unwrap_or(0));
+        let (bottom, top) = (cells.iter().map(|c| c.1).min().unwrap_or(0), cells.iter().map(|c| c.1).max().unwrap_or(0));
+        let col = (self.dimentions.0-(right-left+1))/2-left;
+        let row = (self.visible_height-bottom).min(self.dimentions.1-1-top);
+        (col, row)
+    }
+    /// Hard drops the falling piece and locks it into the board, returning the number of rows it fell.
+    /// The board has no falling piece afterwards until the next one spawns.
+    pub fn drop(&mut self) -> u32{
+        if !self.falling{
+            return 0
+        }
+        let mut cells_dropped = 0;
+        while self.move_down(){cells_dropped+=1};
+        for (col,row) in self.falling_cells(){
+            self.tiles[(row*self.dimentions.0+col) as usize]=Some(self.falling_piece);
+        }
+        self.falling=false;
+        cells_dropped
+    }
+    /// Spawns `new_piece` at the top of the board, one row higher if that spot is taken.
+    /// Returns false if it could not be placed (top out).
+    pub fn new_falling_piece(&mut self, new_piece: TetrisPieceType) -> bool{
+        self.new_falling_piece_rotated(new_piece, 0)
+    }
+    /// Spawns `new_piece` already turned to rotation `rot` (IRS), or in its spawn rotation if it only fits that way.
+    /// A piece that is blocked in its spawn rotation but fits turned is saved from topping out.
+    /// Returns false if it could not be placed either way (top out).
+    pub fn new_falling_piece_rotated(&mut self, new_piece: TetrisPieceType, rot: usize) -> bool{
+        self.falling_piece=new_piece;
+        self.falling=true;
+        for rot in [rot%4, 0]{
+            self.falling_pos=self.spawn_pos(new_piece);
+            self.falling_rot=rot;
+            for dy in [0, 1]{
+                if self.fits(rot, 0, dy){
+                    self.falling_pos.1+=dy;
+                    return true
+                }
+            }
+        }
+        self.falling=false;
+        false
+    }
+}
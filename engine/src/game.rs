@@ -0,0 +1,682 @@
+use std::collections::VecDeque;
+
+use crate::board::TetrisBoard;
+use crate::garbage;
+use crate::input::{Action, Input};
+use crate::master::{self, Grade, Timing};
+use crate::mode::{GameModes, DIG_GARBAGE_ROWS, SURVIVAL_MESSINESS};
+use crate::piece::TetrisPieceType;
+use crate::randomizer::Randomizer;
+use crate::replay::{Replay, TimedInput, ENGINE_VERSION};
+use crate::rng::{self, Rng};
+use crate::scoring::{self, TSpin};
+use crate::settings::Settings;
+
+/// Something that happened during a [`Game::step`] that a frontend may want to react to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameEvent{
+    /// The falling piece locked into the board, clearing `lines_cleared` rows.
+    PieceLocked{lines_cleared: u32},
+    /// The piece that just locked was a T-spin. Sent right before its `PieceLocked`.
+    TSpin{kind: TSpin, lines_cleared: u32},
+    /// The piece that just locked cleared every tile off the board. Sent right before its `PieceLocked`.
+    PerfectClear{lines_cleared: u32},
+    /// The game reached the goal of its mode (or ran out of time in a timed mode), ending it.
+    Finished,
+    /// The next piece could not spawn.
+    GameOver
+}
+
+/// Stream the garbage generator uses, apart from the pieces so that garbage doesn't change the piece sequence.
+const GARBAGE_STREAM: u64 = 1;
+
+/// Timer for an action that repeats while its key is held: a first delay, then a fixed interval.
+#[derive(Clone, Copy)]
+struct AutoRepeat{
+    elapsed: u32,
+    charged: bool
+}
+impl AutoRepeat{
+    fn new() -> Self{
+        AutoRepeat{elapsed: 0, charged: false}
+    }
+    /// Milliseconds until the next repeat, or None once charged with a zero interval (those repeat instantly in `settle`).
+    fn due_in(&self, delay: u32, interval: u32) -> Option<u32>{
+        if !self.charged{
+            Some(delay.saturating_sub(self.elapsed))
+        }else if interval==0{
+            None
+        }else{
+            Some(interval.saturating_sub(self.elapsed))
+        }
+    }
+    fn fire(&mut self){
+        self.charged=true;
+        self.elapsed=0;
+    }
+}
+
+/// The timers `step` can fire, in the order ties are broken.
+#[derive(Clone, Copy)]
+enum Timer{
+    Shift,
+    SoftDrop,
+    Gravity,
+    Lock,
+    LineClear,
+    Entry,
+    TimeLimit,
+    RisingGarbage
+}
+
+/// A single game: the board plus everything around it (queue, held piece, score, gravity, auto-repeat and lock delay).
+///
+/// The game only moves forward through [`Game::step`], so feeding the same inputs with the same time deltas
+/// always produces the same game (given the same pieces).
+pub struct Game{
+    board: TetrisBoard,
+    settings: Settings,
+    seed: u64,
+    piece_rng: Rng,
+    randomizer: Randomizer,
+    garbage_rng: Rng,
+    level: u32,
+    score: u32,
+    lines_cleared: u32,
+    pieces_placed: u32,
+    /// Difficult clears in a row after the first one.
+    back_to_back: u32,
+    /// Whether the last clear was a difficult one, so the next one continues the chain.
+    difficult_last: bool,
+    /// Clears in a row after the first one, or None if the last piece cleared nothing.
+    combo: Option<u32>,
+    max_back_to_back: u32,
+    max_combo: u32,
+    perfect_clears: u32,
+    stick_counter: u32,
+    held_piece: Option<TetrisPieceType>,
+    held_piece_switch_count: u32,
+    piece_queue: VecDeque<TetrisPieceType>,
+    game_over: bool,
+    finished: bool,
+    elapsed_ms: u64,
+    /// Game time at which each multiple of 10 lines was reached.
+    splits: Vec<u64>,
+    gravity_elapsed: u32,
+    lock_elapsed: Option<u32>,
+    left_held: bool,
+    right_held: bool,
+    /// Rotate key being held, for IRS.
+    rotate_held: Option<Action>,
+    /// Whether the hold key is being held, for IHS.
+    hold_held: bool,
+    shift: Option<(Action, AutoRepeat)>,
+    soft_drop: Option<AutoRepeat>,
+    /// Garbage rows put on the board so far.
+    garbage_sent: u32,
+    /// Hole column of the bottom garbage row, which the next rows line up with.
+    garbage_hole: Option<isize>,
+    /// Time since garbage last rose in Survival.
+    garbage_elapsed: u32,
+    /// Number of times garbage has risen in Survival.
+    garbage_rises: u32,
+    /// Whether the last thing that moved the falling piece was a rotation, for T-spins.
+    last_move_rotated: bool,
+    /// How long full rows have been waiting to be cleared, while they are.
+    line_clear_elapsed: Option<u32>,
+    /// How long the next piece has been waiting to enter, while it is.
+    entry_elapsed: Option<u32>,
+    /// Presses made while there was no falling piece, applied in order once the next one enters.
+    buffered: Vec<Action>,
+    /// Master's internal level, which goes up with every piece that enters and every line cleared.
+    master_level: u32,
+    /// Master's TGM-style score, which the grade comes from.
+    master_score: u32,
+    master_combo: u32,
+    /// Rows the falling piece has been soft dropped, for Master's score.
+    soft_rows: u32,
+    /// Game time at which each Master section of 100 levels was finished.
+    sections: Vec<u64>,
+    /// Whether every GM check so far was passed.
+    gm_eligible: bool,
+    /// Every input given while the game was going, for its replay.
+    inputs: Vec<TimedInput>,
+    events: Vec<GameEvent>
+}
+
+impl Game{
+    pub fn new(settings: Settings) -> Self{
+        let seed = settings.seed.unwrap_or_else(rng::random_seed);
+        let mut piece_rng = Rng::new(seed);
+        let mut randomizer = settings.randomizer.start();
+        let first_piece = randomizer.next(&mut piece_rng);
+        let mut board = TetrisBoard::new(settings.board_width.max(4), settings.board_height.max(1), settings.buffer_height, first_piece, settings.rotation_system.clone());
+        board.update_drop_loc();
+        let mut game = Game{board, settings, seed, piece_rng, randomizer, garbage_rng: Rng::with_stream(seed, GARBAGE_STREAM), level: 1, score: 0, lines_cleared: 0, pieces_placed: 0,
+            back_to_back: 0, difficult_last: false, combo: None, max_back_to_back: 0, max_combo: 0, perfect_clears: 0, stick_counter: 0,
+            held_piece: None, held_piece_switch_count: 0, piece_queue: VecDeque::new(), game_over: false, finished: false, elapsed_ms: 0, splits: Vec::new(), gravity_elapsed: 0,
+            lock_elapsed: None, left_held: false, right_held: false, rotate_held: None, hold_held: false, shift: None, soft_drop: None, garbage_sent: 0, garbage_hole: None, garbage_elapsed: 0, garbage_rises: 0, last_move_rotated: false,
+            line_clear_elapsed: None, entry_elapsed: None, buffered: Vec::new(), master_level: 0, master_score: 0, master_combo: 1, soft_rows: 0, sections: Vec::new(), gm_eligible: true, inputs: Vec::new(), events: Vec::new()};
+        game.fill_queue();
+        game.top_up_garbage();
+        game
+    }
+    /// Starts over with a fresh board and queue, keeping the settings. Without a seed in the settings the pieces will be new too.
+    pub fn restart(&mut self){
+        *self = Game::new(self.settings.clone());
+    }
+
+    pub fn board(&self) -> &TetrisBoard{
+        &self.board
+    }
+    pub fn settings(&self) -> &Settings{
+        &self.settings
+    }
+    /// Seed the pieces and garbage of this game came from. Playing with it in the settings gives the same ones again.
+    pub fn seed(&self) -> u64{
+        self.seed
+    }
+    pub fn level(&self) -> u32{
+        self.level
+    }
+    pub fn score(&self) -> u32{
+        self.score
+    }
+    pub fn lines_cleared(&self) -> u32{
+        self.lines_cleared
+    }
+    /// Number of pieces that have locked.
+    pub fn pieces_placed(&self) -> u32{
+        self.pieces_placed
+    }
+    /// Length of the current back-to-back chain: difficult clears in a row after the first.
+    pub fn back_to_back(&self) -> u32{
+        self.back_to_back
+    }
+    /// Current combo: clears in a row after the first.
+    pub fn combo(&self) -> u32{
+        self.combo.unwrap_or(0)
+    }
+    pub fn max_back_to_back(&self) -> u32{
+        self.max_back_to_back
+    }
+    pub fn max_combo(&self) -> u32{
+        self.max_combo
+    }
+    pub fn perfect_clears(&self) -> u32{
+        self.perfect_clears
+    }
+    pub fn held_piece(&self) -> Option<TetrisPieceType>{
+        self.held_piece
+    }
+    pub fn piece_queue(&self) -> &VecDeque<TetrisPieceType>{
+        &self.piece_queue
+    }
+    pub fn is_over(&self) -> bool{
+        self.game_over
+    }
+    /// Whether the game ended by reaching its mode's goal rather than topping out.
+    pub fn is_finished(&self) -> bool{
+        self.finished
+    }
+    /// Total game time in milliseconds that has been stepped through.
+    pub fn elapsed_ms(&self) -> u64{
+        self.elapsed_ms
+    }
+    /// Rows of garbage left to clear in Dig.
+    pub fn garbage_remaining(&self) -> u32{
+        match self.settings.mode{
+            GameModes::Dig{lines, ..} => lines.saturating_sub(self.board.garbage_cleared()),
+            _ => 0
+        }
+    }
+    /// Milliseconds until garbage next rises in Survival.
+    pub fn next_garbage_in(&self) -> Option<u32>{
+        self.settings.mode.garbage_interval(self.garbage_rises).map(|i| i.saturating_sub(self.garbage_elapsed))
+    }
+    /// Game time in milliseconds at which 10, 20, 30... lines were reached.
+    pub fn splits(&self) -> &[u64]{
+        &self.splits
+    }
+    /// Master's internal level, from 0 to 999.
+    pub fn master_level(&self) -> u32{
+        self.master_level
+    }
+    /// Master's TGM-style score, which is kept apart from the usual one.
+    pub fn master_score(&self) -> u32{
+        self.master_score
+    }
+    /// Master grade so far: GM for reaching level 999 having passed every check on the way, otherwise from the score.
+    pub fn grade(&self) -> Grade{
+        if self.gm_eligible && self.master_level>=master::MAX_LEVEL {Grade::GRAND_MASTER} else {Grade::for_score(self.master_score)}
+    }
+    /// Game time in milliseconds at which each Master section (levels 0-99, 100-199...) was finished.
+    pub fn section_times(&self) -> &[u64]{
+        &self.sections
+    }
+    /// Time in milliseconds between gravity ticks at the current level.
+    pub fn tick_speed(&self) -> u32{
+        match self.settings.mode{
+            GameModes::Master => master::tick_speed(self.master_level),
+            _ => scoring::tick_speed(self.level)
+        }
+    }
+    /// Whether full rows are on the board waiting to be cleared.
+    pub fn is_clearing_lines(&self) -> bool{
+        self.line_clear_elapsed.is_some()
+    }
+    /// The game so far as a [`Replay`], which plays back to exactly this state.
+    pub fn replay(&self) -> Replay{
+        Replay{engine_version: ENGINE_VERSION.to_string(), settings: Settings{seed: Some(self.seed), ..self.settings.clone()}, inputs: self.inputs.clone(),
+            length: self.elapsed_ms, score: self.score, lines: self.lines_cleared}
+    }
+
+    /// Advances the game by `elapsed_ms`, firing gravity, auto-repeat and lock delay timers in order,
+    /// then applies `inputs` as having happened at the end of that time.
+    /// Returns what happened along the way.
+    pub fn step(&mut self, elapsed_ms: u32, inputs: &[Input]) -> Vec<GameEvent>{
+        self.run_timers(elapsed_ms);
+        for input in inputs{
+            if self.game_over {break}
+            self.inputs.push(TimedInput{time: self.elapsed_ms, input: *input});
+            self.apply(*input);
+            self.settle();
+            self.run_timers(0);
+        }
+        std::mem::take(&mut self.events)
+    }
+
+    /// Advances time by `ms`, firing every timer that comes due along the way.
+    fn run_timers(&mut self, ms: u32){
+        let mut remaining = ms;
+        while !self.game_over{
+            match self.next_timer(){
+                Some((timer, due)) if due<=remaining => {
+                    self.advance(due);
+                    remaining-=due;
+                    self.fire(timer);
+                    self.settle();
+                }
+                _ => {
+                    self.advance(remaining);
+                    break
+                }
+            }
+        }
+    }
+
+    /// Time between gravity ticks, or None if the mode has no gravity. 0 is 20G.
+    fn gravity(&self) -> Option<u32>{
+        if self.settings.mode.has_gravity() {Some(self.tick_speed())} else {None}
+    }
+    /// Delays for Master's current level, or None in other modes.
+    fn master_timing(&self) -> Option<Timing>{
+        (self.settings.mode==GameModes::Master).then(|| Timing::for_level(self.master_level))
+    }
+    fn lock_delay(&self) -> u32{
+        self.master_timing().map_or(self.settings.lock_delay, |t| t.lock_ms())
+    }
+    fn line_clear_delay(&self) -> u32{
+        self.master_timing().map_or(self.settings.line_clear_delay, |t| t.line_clear_ms())
+    }
+    /// Delay before the next piece enters. Master's can be different right after a line clear.
+    fn entry_delay(&self) -> u32{
+        self.master_timing().map_or(self.settings.entry_delay, |t| if self.combo.is_some() {t.line_are_ms()} else {t.are_ms()})
+    }
+    fn next_timer(&self) -> Option<(Timer, u32)>{
+        let tick = if self.board.has_falling_piece() {self.gravity().unwrap_or(0)} else {0};
+        [
+            (Timer::Shift, self.shift.and_then(|(_,r)| r.due_in(self.settings.hold_time, self.settings.hold_move_interval))),
+            (Timer::SoftDrop, self.soft_drop.and_then(|r| r.due_in(self.settings.down_hold_time, self.settings.down_hold_move_interval))),
+            (Timer::Gravity, if tick>0 {Some(tick.saturating_sub(self.gravity_elapsed))} else {None}),
+            (Timer::Lock, self.lock_elapsed.map(|e| self.lock_delay().saturating_sub(e))),
+            (Timer::LineClear, self.line_clear_elapsed.map(|e| self.line_clear_delay().saturating_sub(e))),
+            (Timer::Entry, self.entry_elapsed.map(|e| self.entry_delay().saturating_sub(e))),
+            (Timer::TimeLimit, self.settings.mode.time_limit().map(|l| l.saturating_sub(self.elapsed_ms).min(u32::MAX as u64) as u32)),
+            (Timer::RisingGarbage, self.next_garbage_in())
+        ].into_iter().filter_map(|(t,due)| due.map(|d| (t,d))).min_by_key(|(_,d)| *d)
+    }
+    /// Moves every running timer on by `ms`. Timers saturate, since a held key or a long game can outlast a `u32`.
+    fn advance(&mut self, ms: u32){
+        self.elapsed_ms+=ms as u64;
+        if self.gravity().is_some() {self.gravity_elapsed=self.gravity_elapsed.saturating_add(ms)}
+        if self.settings.mode.garbage_interval(0).is_some() {self.garbage_elapsed=self.garbage_elapsed.saturating_add(ms)}
+        for e in [self.lock_elapsed.as_mut(), self.line_clear_elapsed.as_mut(), self.entry_elapsed.as_mut()].into_iter().flatten(){
+            *e=e.saturating_add(ms);
+        }
+        if let Some((_,r)) = self.shift.as_mut() {r.elapsed=r.elapsed.saturating_add(ms)}
+        if let Some(r) = self.soft_drop.as_mut() {r.elapsed=r.elapsed.saturating_add(ms)}
+    }
+    fn fire(&mut self, timer: Timer){
+        match timer{
+            Timer::Shift => if let Some((dir, r)) = self.shift.as_mut(){
+                r.fire();
+                let dir = *dir;
+                self.shift_piece(dir);
+            }
+            Timer::SoftDrop => if let Some(r) = self.soft_drop.as_mut(){
+                r.fire();
+                self.soft_drop_once();
+            }
+            Timer::Gravity => {
+                self.gravity_elapsed=0;
+                if self.board.move_down(){
+                    self.last_move_rotated=false;
+                }
+            }
+            Timer::Lock => self.lock_piece(),
+            Timer::LineClear => {
+                self.line_clear_elapsed=None;
+                self.collapse();
+            }
+            Timer::Entry => {
+                self.entry_elapsed=None;
+                self.spawn_next();
+            }
+            Timer::TimeLimit => self.finish(),
+            Timer::RisingGarbage => {
+                self.garbage_elapsed=0;
+                self.garbage_rises+=1;
+                self.push_garbage(1, SURVIVAL_MESSINESS);
+            }
+        }
+    }
+    /// Applies anything that happens instantly (zero repeat intervals, 20G) and starts or cancels the lock delay.
+    fn settle(&mut self){
+        if self.game_over {return}
+        if let Some((dir, r)) = self.shift{
+            if r.charged && self.settings.hold_move_interval==0 { while self.shift_piece(dir){} }
+        }
+        if let Some(r) = self.soft_drop{
+            if r.charged && self.settings.down_hold_move_interval==0 { while self.soft_drop_once(){} }
+        }
+        if self.gravity()==Some(0) { while self.board.move_down(){ self.last_move_rotated=false } }
+        if self.board.has_falling_piece() && self.board.is_grounded() && self.settings.mode.has_lock_delay(){
+            if self.lock_elapsed.is_none(){ self.lock_elapsed=Some(0) }
+        }else{
+            self.lock_elapsed=None;
+        }
+    }
+    fn apply(&mut self, input: Input){
+        match input{
+            Input::Press(action @ (Action::RotateClockwise | Action::RotateCounterClockwise | Action::Rotate180)) => self.rotate_held=Some(action),
+            Input::Release(action) if self.rotate_held==Some(action) => self.rotate_held=None,
+            Input::Press(Action::Hold) => self.hold_held=true,
+            Input::Release(Action::Hold) => self.hold_held=false,
+            _ => {}
+        }
+        if let Input::Press(action) = input{
+            // movement keys are only held, so they already carry over to the next piece
+            if !self.board.has_falling_piece() && !matches!(action, Action::Left | Action::Right | Action::SoftDrop){
+                // with IRS and IHS, keeping the key down until the piece enters does it instead
+                let initial = match action{
+                    Action::Hold => self.settings.ihs,
+                    Action::HardDrop => false,
+                    _ => self.settings.irs
+                };
+                if !initial{
+                    self.buffered.push(action);
+                }
+                return
+            }
+        }
+        match input{
+            Input::Press(Action::Left) => if !self.left_held{
+                self.left_held=true;
+                self.start_shift(Action::Left);
+            }
+            Input::Press(Action::Right) => if !self.right_held{
+                self.right_held=true;
+                self.start_shift(Action::Right);
+            }
+            Input::Release(Action::Left) => {
+                self.left_held=false;
+                if matches!(self.shift, Some((Action::Left,_))){
+                    self.shift = if self.right_held {Some((Action::Right, AutoRepeat::new()))} else {None};
+                }
+            }
+            Input::Release(Action::Right) => {
+                self.right_held=false;
+                if matches!(self.shift, Some((Action::Right,_))){
+                    self.shift = if self.left_held {Some((Action::Left, AutoRepeat::new()))} else {None};
+                }
+            }
+            Input::Press(Action::SoftDrop) => if self.soft_drop.is_none(){
+                self.soft_drop=Some(AutoRepeat::new());
+                self.soft_drop_once();
+            }
+            Input::Release(Action::SoftDrop) => self.soft_drop=None,
+            Input::Press(Action::HardDrop) => self.lock_piece(),
+            Input::Press(Action::RotateClockwise) => if self.board.rotate_clockwise(){
+                self.rotated();
+            }
+            Input::Press(Action::RotateCounterClockwise) => if self.board.rotate_counter_clockwise(){
+                self.rotated();
+            }
+            Input::Press(Action::Rotate180) => if self.board.rotate_180(self.settings.kicks_180){
+                self.rotated();
+            }
+            Input::Press(Action::Hold) => self.hold(),
+            Input::Release(_) => {}
+        }
+    }
+
+    fn start_shift(&mut self, dir: Action){
+        self.shift=Some((dir, AutoRepeat::new()));
+        self.shift_piece(dir);
+    }
+    fn shift_piece(&mut self, dir: Action) -> bool{
+        let moved = match dir{
+            Action::Left => self.board.move_left(),
+            _ => self.board.move_right()
+        };
+        if moved{
+            self.last_move_rotated=false;
+            self.reset_lock();
+        }
+        moved
+    }
+    fn rotated(&mut self){
+        self.last_move_rotated=true;
+        self.reset_lock();
+    }
+    fn soft_drop_once(&mut self) -> bool{
+        if self.board.move_down(){
+            self.last_move_rotated=false;
+            self.score+=scoring::SOFT_DROP_POINTS;
+            self.soft_rows+=1;
+            self.gravity_elapsed=0;
+            return true
+        }
+        false
+    }
+    /// Restarts the lock delay after a successful move or rotation, up to `moves_before_lock` times per piece.
+    fn reset_lock(&mut self){
+        if self.lock_elapsed.is_some() && self.stick_counter<self.settings.moves_before_lock{
+            self.stick_counter+=1;
+            self.lock_elapsed=Some(0);
+        }
+    }
+    /// Drops the piece to the bottom, locks it and scores it. Full rows stay on the board through the line clear delay,
+    /// then the next piece enters after the entry delay.
+    fn lock_piece(&mut self){
+        // a piece that still has to fall moved after its last rotation
+        let t_spin = if self.last_move_rotated && self.board.is_grounded() {self.board.t_spin()} else {None};
+        self.score += self.board.drop()*scoring::HARD_DROP_POINTS;
+        let num_cleared = self.board.full_rows().len() as u32;
+        self.pieces_placed+=1;
+        let mut clear_score = match t_spin{
+            Some(kind) => scoring::t_spin_score(kind, num_cleared, self.level),
+            None => scoring::line_clear_score(num_cleared, self.level)
+        };
+        let perfect_clear = num_cleared>0 && self.board.clears_everything();
+        if num_cleared>0{
+            let difficult = num_cleared>=4 || t_spin.is_some();
+            let back_to_back = difficult && self.difficult_last;
+            if back_to_back{
+                self.back_to_back+=1;
+                clear_score=scoring::back_to_back_bonus(clear_score);
+            }else if !difficult{
+                self.back_to_back=0;
+            }
+            if perfect_clear{
+                self.perfect_clears+=1;
+                clear_score+=scoring::perfect_clear_score(num_cleared, back_to_back, self.level);
+            }
+            self.difficult_last=difficult;
+            let combo = self.combo.map_or(0, |c| c+1);
+            self.combo=Some(combo);
+            self.score += scoring::combo_score(combo, self.level);
+            self.max_back_to_back=self.max_back_to_back.max(self.back_to_back);
+            self.max_combo=self.max_combo.max(combo);
+        }else{
+            self.combo=None;
+        }
+        self.score += clear_score;
+        self.lines_cleared+=num_cleared;
+        while (self.splits.len() as u32+1)*10<=self.lines_cleared{
+            self.splits.push(self.elapsed_ms);
+        }
+        self.level=scoring::level_for_lines(self.lines_cleared);
+        self.advance_master(num_cleared, perfect_clear);
+        if let Some(kind) = t_spin{
+            self.events.push(GameEvent::TSpin{kind, lines_cleared: num_cleared});
+        }
+        if perfect_clear{
+            self.events.push(GameEvent::PerfectClear{lines_cleared: num_cleared});
+        }
+        self.events.push(GameEvent::PieceLocked{lines_cleared: num_cleared});
+        self.lock_elapsed=None;
+        if num_cleared>0 && self.line_clear_delay()>0{
+            self.line_clear_elapsed=Some(0);
+        }else{
+            self.collapse();
+        }
+    }
+    /// Clears the full rows, then ends the game if it reached its goal or gets the next piece ready to enter.
+    fn collapse(&mut self){
+        let cleared = self.board.clear_lines();
+        if self.settings.mode.is_complete(self.lines_cleared, self.board.garbage_cleared(), self.elapsed_ms) || self.master_level>=master::MAX_LEVEL{
+            self.finish();
+            return
+        }
+        if cleared>0{
+            self.top_up_garbage();
+            if self.game_over {return}
+        }
+        if self.entry_delay()>0{
+            self.entry_elapsed=Some(0);
+        }else{
+            self.spawn_next();
+        }
+    }
+    /// Brings in the next piece from the queue once the last one is done with.
+    fn spawn_next(&mut self){
+        self.held_piece_switch_count=0;
+        if self.settings.mode==GameModes::Master{
+            self.master_level=master::level_after_piece(self.master_level);
+        }
+        let mut next = self.next_piece();
+        if self.settings.ihs && self.hold_held && self.settings.max_num_held_piece_switches>0{
+            next=self.swap_held(next);
+        }
+        let rot = match self.rotate_held{
+            Some(Action::RotateClockwise) if self.settings.irs => 1,
+            Some(Action::Rotate180) if self.settings.irs => 2,
+            Some(Action::RotateCounterClockwise) if self.settings.irs => 3,
+            _ => 0
+        };
+        self.spawn(next, rot);
+        for action in std::mem::take(&mut self.buffered){
+            if self.game_over {break}
+            // anything after a buffered hard drop goes back in the buffer for the piece after
+            self.apply(Input::Press(action));
+        }
+    }
+    /// Updates Master's score, level, section times and GM checks for a piece that cleared `lines` lines.
+    fn advance_master(&mut self, lines: u32, bravo: bool){
+        if self.settings.mode!=GameModes::Master {return}
+        self.master_combo=master::next_combo(self.master_combo, lines);
+        if lines==0 {return}
+        self.master_score+=master::clear_score(self.master_level, lines, self.soft_rows, self.master_combo, bravo);
+        let level = master::level_after_clear(self.master_level, lines);
+        for (at, time, score) in master::GRAND_MASTER_CHECKS{
+            if self.master_level<at && level>=at && (self.elapsed_ms>time || self.master_score<score){
+                self.gm_eligible=false;
+            }
+        }
+        while level>=((self.sections.len() as u32+1)*master::SECTION_LEVELS).min(master::MAX_LEVEL) && (self.sections.len() as u32)<master::MAX_LEVEL.div_ceil(master::SECTION_LEVELS){
+            self.sections.push(self.elapsed_ms);
+        }
+        self.master_level=level;
+    }
+    /// Brings the garbage in Dig back up to `DIG_GARBAGE_ROWS` (or half the visible board, if smaller)
+    /// until all of it has been sent.
+    fn top_up_garbage(&mut self){
+        let GameModes::Dig{lines, messiness} = self.settings.mode else {return};
+        let shown = DIG_GARBAGE_ROWS.min(self.board.visible_height() as u32/2);
+        let count = shown.saturating_sub(self.board.garbage_rows()).min(lines-self.garbage_sent.min(lines));
+        if count==0 {return}
+        self.garbage_sent+=count;
+        self.push_garbage(count, messiness);
+    }
+    /// Pushes `count` garbage rows in under the stack, topping out if that pushes anything off the top.
+    fn push_garbage(&mut self, count: u32, messiness: u32){
+        let holes = garbage::garbage_holes(&mut self.garbage_rng, count as usize, self.board.dimentions().0, messiness, self.garbage_hole);
+        self.garbage_hole=holes.first().copied();
+        if !self.board.insert_garbage(&holes){
+            self.game_over=true;
+            self.events.push(GameEvent::GameOver);
+        }
+    }
+    /// Ends the game for having reached the mode's goal.
+    fn finish(&mut self){
+        self.game_over=true;
+        self.finished=true;
+        self.events.push(GameEvent::Finished);
+    }
+    /// Swaps the falling piece with the held piece, if this piece still has switches left.
+    fn hold(&mut self){
+        if self.held_piece_switch_count>=self.settings.max_num_held_piece_switches{
+            return
+        }
+        let next = self.swap_held(self.board.falling_piece());
+        self.spawn(next, 0);
+    }
+    /// Puts `piece` in the hold slot, returning the piece to play instead.
+    fn swap_held(&mut self, piece: TetrisPieceType) -> TetrisPieceType{
+        self.held_piece_switch_count+=1;
+        match self.held_piece.replace(piece){
+            Some(p) => p,
+            None => self.next_piece()
+        }
+    }
+    /// Spawns `piece` in rotation `rot`, or its spawn rotation if it only fits that way.
+    fn spawn(&mut self, piece: TetrisPieceType, rot: usize){
+        self.gravity_elapsed=0;
+        self.last_move_rotated=false;
+        self.lock_elapsed=None;
+        self.stick_counter=0;
+        self.soft_rows=0;
+        while !self.board.new_falling_piece_rotated(piece, rot){
+            if !self.settings.mode.can_top_out() && self.board.remove_top_row(){
+                continue
+            }
+            self.game_over=true;
+            self.events.push(GameEvent::GameOver);
+            return
+        }
+        self.board.update_drop_loc();
+    }
+    fn next_piece(&mut self) -> TetrisPieceType{
+        let next = self.piece_queue.pop_front().unwrap_or(TetrisPieceType::I);
+        self.fill_queue();
+        next
+    }
+    /// Tops the queue up to one more piece than is displayed.
+    fn fill_queue(&mut self){
+        while self.piece_queue.len()<=self.settings.queue_display_len{
+            self.piece_queue.push_back(self.randomizer.next(&mut self.piece_rng));
+        }
+    }
+}
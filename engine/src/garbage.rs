@@ -0,0 +1,22 @@
+use crate::rng::Rng;
+
+/// Hole columns for `count` garbage rows going into a board `width` wide, bottom row first, as taken by
+/// [`TetrisBoard::insert_garbage`](crate::board::TetrisBoard::insert_garbage).
+///
+/// Going down from `above` (the hole of the row they will end up under, if any), each hole has a `messiness`
+/// percent chance of moving to another column; otherwise it lines up with the one above.
+pub fn garbage_holes(rng: &mut Rng, count: usize, width: isize, messiness: u32, above: Option<isize>) -> Vec<isize>{
+    let mut holes = Vec::with_capacity(count);
+    let mut last = above;
+    for _ in 0..count{
+        let hole = match last{
+            Some(h) if width<2 || !rng.chance(messiness) => h,
+            Some(h) => (h+1+rng.below(width as u32-1) as isize).rem_euclid(width),
+            None => rng.below(width.max(1) as u32) as isize
+        };
+        holes.push(hole);
+        last=Some(hole);
+    }
+    holes.reverse();
+    holes
+}
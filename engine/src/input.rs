@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// A player action. Left, right and soft drop auto-repeat while held; the others act once per press.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Action{
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Rotate180,
+    Hold
+}
+
+/// An input event fed to [`crate::Game::step`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Input{
+    Press(Action),
+    Release(Action)
+}
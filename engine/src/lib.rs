@@ -0,0 +1,30 @@
+//! Headless tetris rules: board, pieces, rotation, randomizers, scoring and the game state machine.
+//!
+//! Nothing in here knows about the browser, so it builds and tests on any target.
+
+pub mod board;
+pub mod game;
+pub mod garbage;
+pub mod input;
+pub mod master;
+pub mod mode;
+pub mod piece;
+pub mod randomizer;
+pub mod replay;
+pub mod rng;
+pub mod rotation;
+pub mod scoring;
+pub mod settings;
+pub mod stats;
+
+pub use board::TetrisBoard;
+pub use game::{Game, GameEvent};
+pub use input::{Action, Input};
+pub use mode::GameModes;
+pub use piece::TetrisPieceType;
+pub use randomizer::Randomizers;
+pub use replay::{Playback, Replay};
+pub use rng::Rng;
+pub use rotation::{CustomRotation, Kicks180, RotationSystem, RotationSystems};
+pub use scoring::TSpin;
+pub use settings::Settings;
@@ -0,0 +1,118 @@
+//! Rules for Master mode, after the arcade TGM games: an internal level from 0 to 999, gravity and delays that
+//! change by level, and a grade earned from a score kept the TGM way.
+
+use std::fmt::{Display, Formatter, Result};
+
+/// The level that ends a Master game.
+pub const MAX_LEVEL: u32 = 999;
+/// Levels in a section. Each section stops at its last level until a line is cleared.
+pub const SECTION_LEVELS: u32 = 100;
+
+/// Gravity from each level on, in 1/256ths of a row per frame. 5120 is 20G.
+const GRAVITY: [(u32, u32);30] = [(0,4),(30,6),(35,8),(40,10),(50,12),(60,16),(70,32),(80,48),(90,64),(100,80),(120,96),(140,112),(160,128),
+    (170,144),(200,4),(220,32),(230,64),(233,96),(236,128),(239,160),(243,192),(247,224),(251,256),(300,512),(330,768),(360,1024),
+    (400,1280),(420,1024),(450,768),(500,5120)];
+const TWENTY_G: u32 = 5120;
+
+/// Delays from each level on, in frames.
+const TIMINGS: [(u32, Timing);6] = [
+    (0, Timing{are: 25, line_are: 25, lock: 30, line_clear: 40}),
+    (500, Timing{are: 25, line_are: 25, lock: 30, line_clear: 25}),
+    (600, Timing{are: 25, line_are: 16, lock: 30, line_clear: 16}),
+    (700, Timing{are: 16, line_are: 12, lock: 30, line_clear: 12}),
+    (800, Timing{are: 12, line_are: 6, lock: 30, line_clear: 6}),
+    (900, Timing{are: 12, line_are: 6, lock: 17, line_clear: 6})
+];
+
+/// Score needed for each grade from 9 up to S9.
+const GRADE_SCORES: [u32;18] = [0,400,800,1400,2000,3500,5500,8000,12000,16000,22000,30000,40000,52000,66000,82000,100000,120000];
+/// Level, time (ms) and score a game has to make to stay in the running for GM.
+pub const GRAND_MASTER_CHECKS: [(u32, u64, u32);3] = [(300, 255_000, 12000), (500, 450_000, 40000), (MAX_LEVEL, 810_000, 126000)];
+
+fn frames_to_ms(frames: u32) -> u32{
+    frames*1000/60
+}
+
+/// Entry and lock delays for a level, in frames.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Timing{
+    /// Delay before the next piece enters after one locks without clearing anything.
+    pub are: u32,
+    /// Delay before the next piece enters after a line clear.
+    pub line_are: u32,
+    pub lock: u32,
+    /// How long cleared lines stay on the board before the stack falls.
+    pub line_clear: u32
+}
+impl Timing{
+    pub fn for_level(level: u32) -> Timing{
+        TIMINGS.iter().rev().find(|(from,_)| level>=*from).map_or(TIMINGS[0].1, |(_,t)| *t)
+    }
+    pub fn are_ms(&self) -> u32{
+        frames_to_ms(self.are)
+    }
+    pub fn line_are_ms(&self) -> u32{
+        frames_to_ms(self.line_are)
+    }
+    pub fn lock_ms(&self) -> u32{
+        frames_to_ms(self.lock)
+    }
+    pub fn line_clear_ms(&self) -> u32{
+        frames_to_ms(self.line_clear)
+    }
+}
+
+/// Time in milliseconds for the falling piece to move down one row at `level`, or 0 for 20G.
+pub fn tick_speed(level: u32) -> u32{
+    let g = GRAVITY.iter().rev().find(|(from,_)| level>=*from).map_or(GRAVITY[0].1, |(_,g)| *g);
+    if g>=TWENTY_G {0} else {frames_to_ms(256)/g}
+}
+
+/// The level after a new piece enters at `level`. Pieces can't take the level past the end of a section.
+pub fn level_after_piece(level: u32) -> u32{
+    if level%SECTION_LEVELS==SECTION_LEVELS-1 || level>=MAX_LEVEL-1 {level} else {level+1}
+}
+
+/// The level after clearing `lines` lines at `level`.
+pub fn level_after_clear(level: u32, lines: u32) -> u32{
+    (level+lines).min(MAX_LEVEL)
+}
+
+/// Points for clearing `lines` lines at `level` (before the clear), after soft dropping `soft_rows` rows.
+/// `combo` builds up over consecutive clears and `bravo` is whether the board was left empty.
+pub fn clear_score(level: u32, lines: u32, soft_rows: u32, combo: u32, bravo: bool) -> u32{
+    ((level+lines).div_ceil(4)+soft_rows)*lines*combo*if bravo {4} else {1}
+}
+
+/// The combo multiplier after clearing `lines` lines with `combo` so far. It goes back to 1 on a piece that clears nothing.
+pub fn next_combo(combo: u32, lines: u32) -> u32{
+    if lines==0 {1} else {combo+2*lines-2}
+}
+
+/// A Master grade, from 9 (the lowest) up to 1, then S1 up to S9, then GM.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Grade(u32);
+impl Grade{
+    pub const GRAND_MASTER: Grade = Grade(GRADE_SCORES.len() as u32);
+
+    /// The best grade short of GM that `score` is enough for.
+    pub fn for_score(score: u32) -> Grade{
+        Grade(GRADE_SCORES.iter().rposition(|s| score>=*s).unwrap_or(0) as u32)
+    }
+    /// 0 for grade 9 up to 18 for GM.
+    pub fn rank(&self) -> u32{
+        self.0
+    }
+    pub fn from_rank(rank: u32) -> Grade{
+        Grade(rank.min(Self::GRAND_MASTER.0))
+    }
+}
+impl Display for Grade{
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self.0{
+            0..=8 => write!(f, "{}", 9-self.0),
+            18.. => write!(f, "GM"),
+            s => write!(f, "S{}", s-8)
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::{Display, Formatter, Result};
+
+use serde::{Deserialize, Serialize};
+
+/// Lines it takes to finish Marathon: the end of level 15.
+pub const MARATHON_LINES: u32 = 150;
+/// Most garbage rows Dig keeps on the board at once. More come up as they are cleared.
+pub const DIG_GARBAGE_ROWS: u32 = 10;
+/// Percent of its length each rise takes off Survival's garbage interval.
+const SURVIVAL_SPEEDUP: u32 = 5;
+/// Survival's garbage never rises faster than this, in milliseconds.
+pub const SURVIVAL_MIN_INTERVAL_MS: u32 = 1000;
+/// Percent chance each Survival garbage row's hole moves from the one above it.
+pub const SURVIVAL_MESSINESS: u32 = 30;
+
+/// What the player is going for, and so what ends a game besides topping out.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum GameModes{
+    /// Score as much as possible through level 15, or until topping out if `endless`.
+    Marathon{endless: bool},
+    /// Clear `lines` lines as fast as possible.
+    Sprint{lines: u32},
+    /// Score as much as possible before `duration_ms` runs out.
+    Ultra{duration_ms: u32},
+    /// Stacking practice: no gravity, no lock delay and no topping out.
+    Zen,
+    /// Clear `lines` rows of garbage as fast as possible. `messiness` is the percent chance each row's hole
+    /// is out of line with the one above it.
+    Dig{lines: u32, messiness: u32},
+    /// Last as long as possible while garbage rises from the bottom, first after `interval_ms` and then a bit faster each time.
+    Survival{interval_ms: u32},
+    /// Arcade style: an internal level from 0 to 999 that rises with every piece and line, reaching 20G at 500,
+    /// with shorter delays each section after that. Ends at level 999 with a grade. See [`crate::master`].
+    Master
+}
+impl GameModes{
+    /// Whether a game with `lines_cleared` lines, `garbage_cleared` of them garbage, after `elapsed_ms` has reached this mode's goal.
+    pub fn is_complete(&self, lines_cleared: u32, garbage_cleared: u32, elapsed_ms: u64) -> bool{
+        match self{
+            Self::Marathon{endless} => !endless && lines_cleared>=MARATHON_LINES,
+            Self::Sprint{lines} => lines_cleared>=*lines,
+            Self::Ultra{duration_ms} => elapsed_ms>=*duration_ms as u64,
+            Self::Zen => false,
+            Self::Dig{lines, ..} => garbage_cleared>=*lines,
+            Self::Survival{..} => false,
+            // ends on its internal level, which only the game keeps track of
+            Self::Master => false
+        }
+    }
+    /// Milliseconds until the next garbage row rises after `rises` have already, if garbage rises in this mode.
+    pub fn garbage_interval(&self, rises: u32) -> Option<u32>{
+        match self{
+            Self::Survival{interval_ms} => {
+                // 200 speedups take any sensible interval down to the minimum
+                let shrunk = (0..rises.min(200)).fold(*interval_ms as u64, |i,_| i*(100-SURVIVAL_SPEEDUP) as u64/100) as u32;
+                Some(shrunk.max(SURVIVAL_MIN_INTERVAL_MS.min(*interval_ms)))
+            }
+            _ => None
+        }
+    }
+    /// Whether pieces fall on their own.
+    pub fn has_gravity(&self) -> bool{
+        *self!=Self::Zen
+    }
+    /// Whether grounded pieces lock by themselves after the lock delay, rather than only on hard drop.
+    pub fn has_lock_delay(&self) -> bool{
+        *self!=Self::Zen
+    }
+    /// Whether a blocked spawn ends the game. Otherwise the highest rows of the stack are cleared to make room.
+    pub fn can_top_out(&self) -> bool{
+        *self!=Self::Zen
+    }
+    /// Game time in milliseconds at which the game ends, if it is timed.
+    pub fn time_limit(&self) -> Option<u64>{
+        match self{
+            Self::Ultra{duration_ms} => Some(*duration_ms as u64),
+            _ => None
+        }
+    }
+}
+impl Display for GameModes{
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self{
+            Self::Marathon{endless: false} => write!(f, "Marathon"),
+            Self::Marathon{endless: true} => write!(f, "Marathon (endless)"),
+            Self::Sprint{lines} => write!(f, "Sprint {}L", lines),
+            Self::Ultra{duration_ms} => write!(f, "Ultra {}s", duration_ms/1000),
+            Self::Zen => write!(f, "Zen"),
+            Self::Dig{lines, ..} => write!(f, "Dig {}L", lines),
+            Self::Survival{interval_ms} => write!(f, "Survival {}s", interval_ms/1000),
+            Self::Master => write!(f, "Master")
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::rng::Rng;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TetrisPieceType{
+    I,
+    L,
+    J,
+    O,
+    S,
+    T,
+    Z
+}
+impl TetrisPieceType{
+    pub fn from_int(val: i32) -> Self{
+        match val%7{
+            0 => Self::I,
+            1 => Self::J,
+            2 => Self::L,
+            3 => Self::O,
+            4 => Self::S,
+            5 => Self::T,
+            6 => Self::Z,
+            _ => Self::T
+        }
+    }
+    /// Inverse of [`TetrisPieceType::from_int`].
+    pub fn to_int(&self) -> i32{
+        match self{
+            Self::I => 0,
+            Self::J => 1,
+            Self::L => 2,
+            Self::O => 3,
+            Self::S => 4,
+            Self::T => 5,
+            Self::Z => 6
+        }
+    }
+    pub fn get_random(rng: &mut Rng) -> Self{
+        TetrisPieceType::from_int(rng.below(7) as i32)
+    }
+}
+impl Display for TetrisPieceType{
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f,"{}",match self{
+            Self::I => "I",
+            Self::J => "J",
+            Self::L => "L",
+            Self::O => "O",
+            Self::S => "S",
+            Self::T => "T",
+            Self::Z => "Z"
+        })
+    }
+}
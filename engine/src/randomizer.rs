@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter, Result};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::piece::TetrisPieceType;
+use crate::rng::Rng;
+
+/// Copies of each piece in the TGM3 pool.
+const TGM3_POOL_COPIES: usize = 5;
+
+/// How the piece sequence is generated. [`Randomizers::start`] gives the generator for a game.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub enum Randomizers{
+    /// 7-bag: each run of 7 pieces has one of every piece.
+    RandomGenerator,
+    /// Every piece equally likely every time.
+    Random,
+    /// Like the 7-bag, but with two of every piece in each bag of 14.
+    Bag14,
+    /// A 7-bag plus one extra random piece, shuffled together.
+    Bag7Plus1,
+    /// TGM1: rolls up to 4 times for a piece that isn't one of the last 4.
+    Tgm1,
+    /// TGM3: draws from a pool of 35 with a 4 piece history, refilling the pool with whatever has gone longest unseen.
+    Tgm3,
+    /// NES: rerolls once if it picks the same piece as last time.
+    Nes
+}
+impl Randomizers{
+    /// A fresh generator of this kind.
+    pub fn start(&self) -> Randomizer{
+        let mut pool = Vec::new();
+        let mut history = VecDeque::new();
+        if *self==Self::Tgm3{
+            pool=(0..7*TGM3_POOL_COPIES).map(|i| TetrisPieceType::from_int((i%7) as i32)).collect();
+            history.extend([TetrisPieceType::Z, TetrisPieceType::S, TetrisPieceType::Z, TetrisPieceType::S]);
+        }else if *self==Self::Tgm1{
+            history.extend([TetrisPieceType::Z;4]);
+        }
+        Randomizer{kind: self.clone(), bag: Vec::new(), history, pool, droughts: [0;7], first: true}
+    }
+}
+impl Display for Randomizers{
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f,"{}",match &self{
+            Self::RandomGenerator => "RandomGenerator",
+            Self::Random => "Random",
+            Self::Bag14 => "Bag14",
+            Self::Bag7Plus1 => "Bag7Plus1",
+            Self::Tgm1 => "TGM1",
+            Self::Tgm3 => "TGM3",
+            Self::Nes => "NES"
+        })
+    }
+}
+impl FromStr for Randomizers{
+    type Err = String;
+    /// Reads a name as [`Display`] writes it, in any case.
+    fn from_str(s: &str) -> std::result::Result<Self, String>{
+        [Self::RandomGenerator, Self::Random, Self::Bag14, Self::Bag7Plus1, Self::Tgm1, Self::Tgm3, Self::Nes].into_iter()
+            .find(|r| r.to_string().eq_ignore_ascii_case(s.trim())).ok_or_else(|| format!("unknown randomizer {}", s))
+    }
+}
+
+/// The state of a piece sequence as it is being generated: the rest of the bag, recent pieces and such.
+#[derive(Clone, Debug)]
+pub struct Randomizer{
+    kind: Randomizers,
+    /// Pieces left in the current bag, taken from the end.
+    bag: Vec<TetrisPieceType>,
+    /// Most recent pieces, newest last.
+    history: VecDeque<TetrisPieceType>,
+    /// TGM3's pool of 35.
+    pool: Vec<TetrisPieceType>,
+    /// Pieces dealt since each piece was last seen, for TGM3.
+    droughts: [u32;7],
+    first: bool
+}
+impl Randomizer{
+    pub fn kind(&self) -> &Randomizers{
+        &self.kind
+    }
+    pub fn next(&mut self, rng: &mut Rng) -> TetrisPieceType{
+        let piece = match self.kind{
+            Randomizers::RandomGenerator => self.draw_from_bag(rng, 1, 0),
+            Randomizers::Bag14 => self.draw_from_bag(rng, 2, 0),
+            Randomizers::Bag7Plus1 => self.draw_from_bag(rng, 1, 1),
+            Randomizers::Random => TetrisPieceType::get_random(rng),
+            Randomizers::Tgm1 => self.tgm1(rng),
+            Randomizers::Tgm3 => self.tgm3(rng),
+            Randomizers::Nes => {
+                // the eighth roll stands for "none", which rerolls like a repeat does
+                let roll = rng.below(8) as i32;
+                if roll==7 || self.history.back()==Some(&TetrisPieceType::from_int(roll)){
+                    TetrisPieceType::get_random(rng)
+                }else{
+                    TetrisPieceType::from_int(roll)
+                }
+            }
+        };
+        self.first=false;
+        self.history.push_back(piece);
+        if self.history.len()>4{
+            self.history.pop_front();
+        }
+        piece
+    }
+    /// Takes the next piece from a bag of `copies` of every piece plus `extra` random ones, refilling it when empty.
+    fn draw_from_bag(&mut self, rng: &mut Rng, copies: usize, extra: usize) -> TetrisPieceType{
+        if self.bag.is_empty(){
+            self.bag.extend((0..7*copies).map(|i| TetrisPieceType::from_int((i%7) as i32)));
+            self.bag.extend((0..extra).map(|_| TetrisPieceType::get_random(rng)));
+            // Fisher-Yates, so every order is equally likely
+            for i in (1..self.bag.len()).rev(){
+                let j = rng.below(i as u32+1) as usize;
+                self.bag.swap(i, j);
+            }
+        }
+        self.bag.pop().unwrap_or(TetrisPieceType::I)
+    }
+    /// TGM games never start with S, Z or O, which can't go down flat on an empty board.
+    fn first_piece(rng: &mut Rng) -> TetrisPieceType{
+        [TetrisPieceType::I, TetrisPieceType::J, TetrisPieceType::L, TetrisPieceType::T][rng.below(4) as usize]
+    }
+    fn tgm1(&mut self, rng: &mut Rng) -> TetrisPieceType{
+        if self.first{
+            return Self::first_piece(rng)
+        }
+        let mut piece = TetrisPieceType::get_random(rng);
+        for _ in 1..4{
+            if !self.history.contains(&piece) {break}
+            piece=TetrisPieceType::get_random(rng);
+        }
+        piece
+    }
+    fn tgm3(&mut self, rng: &mut Rng) -> TetrisPieceType{
+        if self.first{
+            let piece = Self::first_piece(rng);
+            self.note_drought(piece);
+            return piece
+        }
+        let mut idx = rng.below(self.pool.len() as u32) as usize;
+        for _ in 1..6{
+            if !self.history.contains(&self.pool[idx]) {break}
+            // a rejected piece makes room in the pool for the one that is most overdue
+            self.pool[idx]=self.most_droughted();
+            idx=rng.below(self.pool.len() as u32) as usize;
+        }
+        let piece = self.pool[idx];
+        self.note_drought(piece);
+        self.pool[idx]=self.most_droughted();
+        piece
+    }
+    fn note_drought(&mut self, piece: TetrisPieceType){
+        for d in self.droughts.iter_mut(){
+            *d+=1;
+        }
+        self.droughts[piece.to_int() as usize]=0;
+    }
+    /// The piece that has gone longest without being dealt, the first of them on a tie.
+    fn most_droughted(&self) -> TetrisPieceType{
+        let longest = self.droughts.iter().copied().max().unwrap_or(0);
+        TetrisPieceType::from_int(self.droughts.iter().position(|d| *d==longest).unwrap_or(0) as i32)
+    }
+}
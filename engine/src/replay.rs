@@ -0,0 +1,253 @@
+//! Recorded games and playing them back.
+//!
+//! A game only depends on its settings, its seed and the inputs given to [`Game::step`] at each point in game time,
+//! so that is all a [`Replay`] keeps, along with the result it claims. Every game records one as it goes; see [`Game::replay`].
+//!
+//! # File format
+//!
+//! Replay files come in two encodings of the same fields: JSON, for reading and for tools, and a compact binary one
+//! for saving and sending. In order:
+//!
+//! - `format_version`: [`FORMAT_VERSION`], bumped whenever the layout of the file changes. Files from another
+//!   version are refused before anything else is read.
+//! - `header`:
+//!   - `engine_version`: version of `tetris-engine` that recorded the game, for information.
+//!   - `ruleset`: [`RULESET`], bumped whenever a change to the engine makes the same inputs play out differently.
+//!     Replays from another ruleset are refused rather than played wrong.
+//!   - `seed`: seed of the pieces and garbage.
+//!   - `settings`: every [`Settings`] field but `seed`, named as in the struct, with enums as `"Variant"` or
+//!     `{"Variant": fields}`. Replays with settings outside what [`Settings::check`] allows are refused.
+//! - `result`, as claimed by whoever recorded the game:
+//!   - `score` and `lines` at the end of the recording.
+//!   - `time_ms`: game time at which the recording ends, at most [`MAX_LENGTH_MS`].
+//! - `events`: every input as `[delta_ms, input]`, where `delta_ms` is the game time since the input before it
+//!   (or the start) and `input` is `{"Press": action}` or `{"Release": action}` with an [`crate::Action`] name.
+//!
+//! ```json
+//! {"format_version":1,"header":{"engine_version":"0.1.0","ruleset":1,"seed":42,"settings":{"hold_time":150,...}},
+//!  "result":{"score":1200,"lines":12,"time_ms":61234},"events":[[412,{"Press":"Left"}],[96,{"Release":"Left"}],...]}
+//! ```
+//!
+//! The binary encoding is the bytes `TTRP` followed by the same fields in the
+//! [postcard wire format](https://postcard.jamesmunns.com/wire-format): unsigned integers as LEB128 varints, signed
+//! ones zigzagged first, strings and lists prefixed with their length, options and enums as a varint of the variant
+//! index followed by its fields, and structs and tuples as their fields in order. A typical input takes three or four bytes.
+//!
+//! [`Replay::verify`] plays a replay through a fresh game to check its claimed result, so that made up or edited
+//! results can be turned away.
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Game, GameEvent};
+use crate::input::Input;
+use crate::settings::Settings;
+
+/// Version of the replay file layout.
+pub const FORMAT_VERSION: u32 = 1;
+/// Revision of the game rules, which replays have to match to play out the same.
+pub const RULESET: u32 = 1;
+/// First bytes of a binary replay.
+pub const MAGIC: &[u8;4] = b"TTRP";
+/// Version of this engine, written into the replays it records.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Longest replay that can be read, a day of game time, so that checking one can't be made to take forever.
+pub const MAX_LENGTH_MS: u64 = 24*60*60*1000;
+
+/// An input and the game time it was given at, in milliseconds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimedInput{
+    pub time: u64,
+    pub input: Input
+}
+
+/// Everything needed to play a game again exactly, and what it claims to have scored.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Replay{
+    /// Version of the engine that recorded the game.
+    pub engine_version: String,
+    /// Settings the game was played with. The seed is always set.
+    pub settings: Settings,
+    /// Inputs in the order they were given.
+    pub inputs: Vec<TimedInput>,
+    /// Game time at which the recording ends.
+    pub length: u64,
+    /// Score at the end of the recording.
+    pub score: u32,
+    /// Lines cleared by the end of the recording.
+    pub lines: u32
+}
+impl Replay{
+    pub fn to_json(&self) -> String{
+        serde_json::to_string(&self.to_file()).expect("replays always serialize")
+    }
+    /// The binary encoding, as described in the [module docs](self).
+    pub fn to_bytes(&self) -> Vec<u8>{
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(postcard::to_allocvec(&self.to_file()).expect("replays always serialize"));
+        bytes
+    }
+    pub fn from_json(json: &str) -> std::result::Result<Self, String>{
+        let value: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("not a replay: {}", e))?;
+        check_format_version(value.get("format_version").and_then(|v| v.as_u64()))?;
+        Replay::from_file(serde_json::from_value(value).map_err(|e| format!("not a replay: {}", e))?)
+    }
+    pub fn from_bytes(bytes: &[u8]) -> std::result::Result<Self, String>{
+        let rest = bytes.strip_prefix(MAGIC).ok_or("not a replay: wrong magic bytes")?;
+        check_format_version(postcard::take_from_bytes::<u32>(rest).ok().map(|(v,_)| v as u64))?;
+        Replay::from_file(postcard::from_bytes(rest).map_err(|e| format!("not a replay: {}", e))?)
+    }
+    /// Reads a replay in either encoding.
+    pub fn decode(bytes: &[u8]) -> std::result::Result<Self, String>{
+        if bytes.starts_with(MAGIC){
+            Replay::from_bytes(bytes)
+        }else{
+            Replay::from_json(std::str::from_utf8(bytes).map_err(|_| "not a replay: neither binary nor text")?)
+        }
+    }
+    /// Plays the replay through a fresh game and checks that it ends with the score, lines and time it claims.
+    /// Returns the game as it ends, or what didn't match.
+    pub fn verify(&self) -> std::result::Result<Game, String>{
+        if self.inputs.last().is_some_and(|i| i.time>self.length){
+            return Err(String::from("there are inputs after the end of the replay"))
+        }
+        let mut playback = Playback::new(self.clone());
+        playback.seek(self.length);
+        let game = playback.game;
+        let mismatches: Vec<String> = [("score", self.score as u64, game.score() as u64), ("lines", self.lines as u64, game.lines_cleared() as u64),
+            ("time in ms", self.length, game.elapsed_ms())].into_iter()
+            .filter(|(_, claimed, actual)| claimed!=actual)
+            .map(|(what, claimed, actual)| format!("{} claimed as {} but played out as {}", what, claimed, actual)).collect();
+        if mismatches.is_empty() {Ok(game)} else {Err(mismatches.join(", "))}
+    }
+
+    fn to_file(&self) -> ReplayFile{
+        let mut last = 0;
+        let events = self.inputs.iter().map(|i| {
+            let delta = i.time.saturating_sub(last);
+            last = i.time;
+            (delta, i.input)
+        }).collect();
+        ReplayFile{format_version: FORMAT_VERSION, header: Header{engine_version: self.engine_version.clone(), ruleset: RULESET, seed: self.settings.seed.unwrap_or(0), settings: self.settings.clone()},
+            result: ClaimedResult{score: self.score, lines: self.lines, time_ms: self.length}, events}
+    }
+    fn from_file(file: ReplayFile) -> std::result::Result<Self, String>{
+        let header = file.header;
+        if header.ruleset!=RULESET{
+            return Err(format!("recorded with ruleset {} by engine {}, but this engine plays ruleset {}", header.ruleset, header.engine_version, RULESET))
+        }
+        header.settings.check().map_err(|e| format!("unsupported settings: {}", e))?;
+        if file.result.time_ms>MAX_LENGTH_MS{
+            return Err(format!("replay is {} ms long, but at most {} ms can be played back", file.result.time_ms, MAX_LENGTH_MS))
+        }
+        let mut time = 0u64;
+        let inputs = file.events.into_iter().map(|(delta, input)| {
+            time = time.saturating_add(delta);
+            TimedInput{time, input}
+        }).collect();
+        Ok(Replay{engine_version: header.engine_version, settings: Settings{seed: Some(header.seed), ..header.settings}, inputs,
+            length: file.result.time_ms, score: file.result.score, lines: file.result.lines})
+    }
+}
+
+fn check_format_version(version: Option<u64>) -> std::result::Result<(), String>{
+    match version{
+        Some(v) if v==FORMAT_VERSION as u64 => Ok(()),
+        Some(v) => Err(format!("replay format {} can't be read, only format {}", v, FORMAT_VERSION)),
+        None => Err(String::from("not a replay: no format version"))
+    }
+}
+
+/// A replay as it is laid out in a file. See the [module docs](self).
+#[derive(Serialize, Deserialize)]
+struct ReplayFile{
+    format_version: u32,
+    header: Header,
+    result: ClaimedResult,
+    events: Vec<(u64, Input)>
+}
+#[derive(Serialize, Deserialize)]
+struct Header{
+    engine_version: String,
+    ruleset: u32,
+    seed: u64,
+    settings: Settings
+}
+#[derive(Serialize, Deserialize)]
+struct ClaimedResult{
+    score: u32,
+    lines: u32,
+    time_ms: u64
+}
+
+/// A replay being played back into a fresh game, which can be moved to any point in it.
+pub struct Playback{
+    replay: Replay,
+    game: Game,
+    /// Index of the next input to give the game.
+    next: usize,
+    /// Game time played back so far.
+    time: u64
+}
+impl Playback{
+    pub fn new(replay: Replay) -> Self{
+        Playback{game: Game::new(replay.settings.clone()), replay, next: 0, time: 0}
+    }
+    pub fn replay(&self) -> &Replay{
+        &self.replay
+    }
+    /// The game as it was at [`Playback::time`].
+    pub fn game(&self) -> &Game{
+        &self.game
+    }
+    pub fn time(&self) -> u64{
+        self.time
+    }
+    /// Whether the whole replay has been played.
+    pub fn is_done(&self) -> bool{
+        self.time>=self.replay.length
+    }
+    /// Plays on for `ms` of game time. Returns what happened along the way.
+    pub fn advance(&mut self, ms: u64) -> Vec<GameEvent>{
+        self.seek(self.time.saturating_add(ms))
+    }
+    /// Moves to game time `time`, clamped to the length of the replay. Going back plays again from the start.
+    /// Returns what happened along the way.
+    pub fn seek(&mut self, time: u64) -> Vec<GameEvent>{
+        let time = time.min(self.replay.length);
+        if time<self.time{
+            self.game=Game::new(self.replay.settings.clone());
+            self.next=0;
+            self.time=0;
+        }
+        let mut events = Vec::new();
+        loop{
+            match self.replay.inputs.get(self.next){
+                Some(next) if next.time<=time && !self.game.is_over() => {
+                    // inputs given in the same step all happened at the same time
+                    let at = next.time.max(self.time);
+                    let count = self.replay.inputs[self.next..].iter().take_while(|i| i.time<=at).count();
+                    let inputs: Vec<Input> = self.replay.inputs[self.next..self.next+count].iter().map(|i| i.input).collect();
+                    self.next+=count;
+                    events.extend(self.step_to(at, &inputs));
+                }
+                _ => {
+                    events.extend(self.step_to(time, &[]));
+                    return events
+                }
+            }
+        }
+    }
+    /// Steps the game from `self.time` to `time` and then gives it `inputs`. Once the game is over there is nothing left to step.
+    fn step_to(&mut self, time: u64, inputs: &[Input]) -> Vec<GameEvent>{
+        let mut events = Vec::new();
+        while !self.game.is_over() && time-self.time>u32::MAX as u64{
+            events.extend(self.game.step(u32::MAX, &[]));
+            self.time+=u32::MAX as u64;
+        }
+        if !self.game.is_over(){
+            events.extend(self.game.step((time-self.time) as u32, inputs));
+        }
+        self.time=time;
+        events
+    }
+}
@@ -0,0 +1,54 @@
+//! The random number generator behind pieces and garbage.
+//!
+//! It is a PCG32 written out here rather than taken from `rand`, whose generators may change between versions,
+//! so a seed gives the same game on every platform and build.
+
+const MULTIPLIER: u64 = 6364136223846793005;
+/// Stream used by [`Rng::new`], the one from the PCG reference code.
+const DEFAULT_STREAM: u64 = 721347520444481703;
+
+/// A small seeded generator (PCG32, XSH RR variant).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Rng{
+    state: u64,
+    increment: u64
+}
+impl Rng{
+    pub fn new(seed: u64) -> Self{
+        Self::with_stream(seed, DEFAULT_STREAM)
+    }
+    /// A generator on its own `stream`, so that generators sharing a seed don't repeat each other.
+    pub fn with_stream(seed: u64, stream: u64) -> Self{
+        let mut rng = Rng{state: 0, increment: (stream<<1) | 1};
+        rng.next_u32();
+        rng.state=rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+    pub fn next_u32(&mut self) -> u32{
+        let old = self.state;
+        self.state=old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old>>18)^old)>>27) as u32;
+        xorshifted.rotate_right((old>>59) as u32)
+    }
+    /// A number from 0 up to but not including `n`, with every value equally likely. `n` must not be 0.
+    pub fn below(&mut self, n: u32) -> u32{
+        // values under the threshold would make the low results a little more likely
+        let threshold = n.wrapping_neg()%n;
+        loop{
+            let r = self.next_u32();
+            if r>=threshold{
+                return r%n
+            }
+        }
+    }
+    /// True `percent` percent of the time.
+    pub fn chance(&mut self, percent: u32) -> bool{
+        self.below(100)<percent
+    }
+}
+
+/// A fresh seed for a game that wasn't given one.
+pub fn random_seed() -> u64{
+    rand::random()
+}
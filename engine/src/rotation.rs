@@ -0,0 +1,420 @@
+use std::fmt::{Display, Formatter, Result};
+
+use serde::{Deserialize, Serialize};
+
+use crate::piece::TetrisPieceType;
+
+/// How pieces look in each rotation and which kicks they try when turning.
+///
+/// Rotations are numbered clockwise from the spawn state: 0 (spawn), 1 (R), 2, 3 (L).
+pub trait RotationSystem{
+    /// Cells occupied by `piece` in rotation `rot`, as (column, row) offsets in a 4x4 box with row 0 at the bottom.
+    fn cells(&self, piece: TetrisPieceType, rot: usize) -> [(isize,isize);4];
+    /// Offsets to try, in order, when turning `piece` a quarter turn from rotation `from` to rotation `to`.
+    /// `blocked` says whether a cell of the 4x4 box (same offsets as [`RotationSystem::cells`]) is off the board
+    /// or taken before any kick is applied, for systems whose kicks depend on what is in the way.
+    fn kicks(&self, piece: TetrisPieceType, from: usize, to: usize, blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>;
+    /// Offsets to try when turning a half turn away from rotation `from`. Defaults to the `kicks_180` table.
+    fn half_turn_kicks(&self, piece: TetrisPieceType, from: usize, kicks_180: Kicks180, _blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        kicks_180.kicks(piece, from)
+    }
+}
+
+/// The Super Rotation System from the tetris guideline.
+pub struct Srs;
+impl RotationSystem for Srs{
+    fn cells(&self, piece: TetrisPieceType, rot: usize) -> [(isize,isize);4]{
+        match rot%4{
+            0 => match piece{
+                TetrisPieceType::I => [(0,2),(1,2),(2,2),(3,2)],
+                TetrisPieceType::L => [(0,1),(1,1),(2,1),(2,2)],
+                TetrisPieceType::J => [(0,1),(1,1),(2,1),(0,2)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(0,1),(1,1),(1,2),(2,2)],
+                TetrisPieceType::T => [(0,1),(1,1),(2,1),(1,2)],
+                TetrisPieceType::Z => [(1,1),(2,1),(0,2),(1,2)],
+            }
+            1 => match piece{
+                TetrisPieceType::I => [(2,0),(2,1),(2,2),(2,3)],
+                TetrisPieceType::L => [(1,0),(2,0),(1,1),(1,2)],
+                TetrisPieceType::J => [(1,0),(1,1),(1,2),(2,2)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(2,0),(1,1),(2,1),(1,2)],
+                TetrisPieceType::T => [(1,0),(1,1),(2,1),(1,2)],
+                TetrisPieceType::Z => [(1,0),(1,1),(2,1),(2,2)],
+            }
+            2 => match piece{
+                TetrisPieceType::I => [(0,1),(1,1),(2,1),(3,1)],
+                TetrisPieceType::L => [(0,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::J => [(2,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(0,0),(1,0),(1,1),(2,1)],
+                TetrisPieceType::T => [(1,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::Z => [(1,0),(2,0),(0,1),(1,1)],
+            }
+            _ => match piece{
+                TetrisPieceType::I => [(1,0),(1,1),(1,2),(1,3)],
+                TetrisPieceType::L => [(1,0),(1,1),(0,2),(1,2)],
+                TetrisPieceType::J => [(0,0),(1,0),(1,1),(1,2)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(1,0),(0,1),(1,1),(0,2)],
+                TetrisPieceType::T => [(1,0),(0,1),(1,1),(1,2)],
+                TetrisPieceType::Z => [(0,0),(0,1),(1,1),(1,2)],
+            }
+        }
+    }
+    fn kicks(&self, piece: TetrisPieceType, from: usize, to: usize, _blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        match piece{
+            TetrisPieceType::O => vec![(0,0)],
+            TetrisPieceType::I => match (from%4,to%4){
+                (0,1) => vec![(0,0),(-2,0),(1,0),(-2,-1),(1,2)],
+                (1,0) => vec![(0,0),(2,0),(-1,0),(2,1),(-1,-2)],
+                (1,2) => vec![(0,0),(-1,0),(2,0),(-1,2),(2,-1)],
+                (2,1) => vec![(0,0),(1,0),(-2,0),(1,-2),(-2,1)],
+                (2,3) => vec![(0,0),(2,0),(-1,0),(2,1),(-1,-2)],
+                (3,2) => vec![(0,0),(-2,0),(1,0),(-2,-1),(1,2)],
+                (3,0) => vec![(0,0),(1,0),(-2,0),(1,-2),(-2,1)],
+                (0,3) => vec![(0,0),(-1,0),(2,0),(-1,2),(2,-1)],
+                _ => vec![(0,0)]
+            }
+            _ => match (from%4,to%4){
+                (0,1) => vec![(0,0),(-1,0),(-1,1),(0,-2),(-1,-2)],
+                (1,0) => vec![(0,0),(1,0),(1,-1),(0,2),(1,2)],
+                (1,2) => vec![(0,0),(1,0),(1,-1),(0,2),(1,2)],
+                (2,1) => vec![(0,0),(-1,0),(-1,1),(0,-2),(-1,-2)],
+                (2,3) => vec![(0,0),(1,0),(1,1),(0,-2),(1,-2)],
+                (3,2) => vec![(0,0),(-1,0),(-1,-1),(0,2),(-1,2)],
+                (3,0) => vec![(0,0),(-1,0),(-1,-1),(0,2),(-1,2)],
+                (0,3) => vec![(0,0),(1,0),(1,1),(0,-2),(1,-2)],
+                _ => vec![(0,0)]
+            }
+        }
+    }
+}
+
+/// The Arika Rotation System used by the TGM games.
+///
+/// Pieces spawn flat side up and sit at the bottom of their 3x3 box in every rotation. A blocked rotation tries one
+/// column right, then one column left; I never kicks. L, J and T do not kick either when the first blocked cell of the
+/// new rotation (reading the box top to bottom, left to right) is in the center column.
+pub struct Ars;
+impl Ars{
+    fn center_column_blocked(&self, piece: TetrisPieceType, to: usize, blocked: &dyn Fn(isize,isize) -> bool) -> bool{
+        let cells = self.cells(piece, to);
+        for row in (0..3).rev(){
+            for col in 0..3{
+                if cells.contains(&(col,row)) && blocked(col,row){
+                    return col==1
+                }
+            }
+        }
+        false
+    }
+}
+impl RotationSystem for Ars{
+    fn cells(&self, piece: TetrisPieceType, rot: usize) -> [(isize,isize);4]{
+        match rot%4{
+            0 => match piece{
+                TetrisPieceType::I => [(0,2),(1,2),(2,2),(3,2)],
+                TetrisPieceType::L => [(0,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::J => [(2,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(0,0),(1,0),(1,1),(2,1)],
+                TetrisPieceType::T => [(1,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::Z => [(1,0),(2,0),(0,1),(1,1)],
+            }
+            1 => match piece{
+                TetrisPieceType::I => [(2,0),(2,1),(2,2),(2,3)],
+                TetrisPieceType::L => [(1,0),(1,1),(0,2),(1,2)],
+                TetrisPieceType::J => [(0,0),(1,0),(1,1),(1,2)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(1,0),(0,1),(1,1),(0,2)],
+                TetrisPieceType::T => [(1,0),(0,1),(1,1),(1,2)],
+                TetrisPieceType::Z => [(1,0),(1,1),(2,1),(2,2)],
+            }
+            2 => match piece{
+                TetrisPieceType::I => [(0,2),(1,2),(2,2),(3,2)],
+                TetrisPieceType::L => [(0,0),(1,0),(2,0),(2,1)],
+                TetrisPieceType::J => [(0,0),(1,0),(2,0),(0,1)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(0,0),(1,0),(1,1),(2,1)],
+                TetrisPieceType::T => [(0,0),(1,0),(2,0),(1,1)],
+                TetrisPieceType::Z => [(1,0),(2,0),(0,1),(1,1)],
+            }
+            _ => match piece{
+                TetrisPieceType::I => [(2,0),(2,1),(2,2),(2,3)],
+                TetrisPieceType::L => [(1,0),(2,0),(1,1),(1,2)],
+                TetrisPieceType::J => [(1,0),(1,1),(1,2),(2,2)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(1,0),(0,1),(1,1),(0,2)],
+                TetrisPieceType::T => [(1,0),(1,1),(2,1),(1,2)],
+                TetrisPieceType::Z => [(1,0),(1,1),(2,1),(2,2)],
+            }
+        }
+    }
+    fn kicks(&self, piece: TetrisPieceType, _from: usize, to: usize, blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        match piece{
+            TetrisPieceType::I|TetrisPieceType::O => vec![(0,0)],
+            TetrisPieceType::L|TetrisPieceType::J|TetrisPieceType::T if self.center_column_blocked(piece, to, blocked) => vec![(0,0)],
+            _ => vec![(0,0),(1,0),(-1,0)]
+        }
+    }
+    fn half_turn_kicks(&self, piece: TetrisPieceType, from: usize, _kicks_180: Kicks180, blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        self.kicks(piece, from, (from+2)%4, blocked)
+    }
+}
+
+/// Right-handed Nintendo rotation from the NES and Game Boy games: pieces turn about a fixed cell and never kick.
+/// S, Z and I only have two rotations and stand to the right of center when vertical.
+pub struct Classic;
+impl RotationSystem for Classic{
+    fn cells(&self, piece: TetrisPieceType, rot: usize) -> [(isize,isize);4]{
+        match rot%4{
+            0 => match piece{
+                TetrisPieceType::I => [(0,1),(1,1),(2,1),(3,1)],
+                TetrisPieceType::L => [(0,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::J => [(2,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(0,0),(1,0),(1,1),(2,1)],
+                TetrisPieceType::T => [(1,0),(0,1),(1,1),(2,1)],
+                TetrisPieceType::Z => [(1,0),(2,0),(0,1),(1,1)],
+            }
+            1 => match piece{
+                TetrisPieceType::I => [(2,0),(2,1),(2,2),(2,3)],
+                TetrisPieceType::L => [(1,0),(1,1),(0,2),(1,2)],
+                TetrisPieceType::J => [(0,0),(1,0),(1,1),(1,2)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(2,0),(1,1),(2,1),(1,2)],
+                TetrisPieceType::T => [(1,0),(0,1),(1,1),(1,2)],
+                TetrisPieceType::Z => [(1,0),(1,1),(2,1),(2,2)],
+            }
+            2 => match piece{
+                TetrisPieceType::I => [(0,1),(1,1),(2,1),(3,1)],
+                TetrisPieceType::L => [(0,1),(1,1),(2,1),(2,2)],
+                TetrisPieceType::J => [(0,1),(1,1),(2,1),(0,2)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(0,0),(1,0),(1,1),(2,1)],
+                TetrisPieceType::T => [(0,1),(1,1),(2,1),(1,2)],
+                TetrisPieceType::Z => [(1,0),(2,0),(0,1),(1,1)],
+            }
+            _ => match piece{
+                TetrisPieceType::I => [(2,0),(2,1),(2,2),(2,3)],
+                TetrisPieceType::L => [(1,0),(2,0),(1,1),(1,2)],
+                TetrisPieceType::J => [(1,0),(1,1),(1,2),(2,2)],
+                TetrisPieceType::O => [(1,0),(2,0),(1,1),(2,1)],
+                TetrisPieceType::S => [(2,0),(1,1),(2,1),(1,2)],
+                TetrisPieceType::T => [(1,0),(1,1),(2,1),(1,2)],
+                TetrisPieceType::Z => [(1,0),(1,1),(2,1),(2,2)],
+            }
+        }
+    }
+    fn kicks(&self, _piece: TetrisPieceType, _from: usize, _to: usize, _blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        vec![(0,0)]
+    }
+    fn half_turn_kicks(&self, _piece: TetrisPieceType, _from: usize, _kicks_180: Kicks180, _blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        vec![(0,0)]
+    }
+}
+
+/// A rotation system defined by data, starting from one of the built in systems.
+///
+/// Definitions are text with one entry per line. Blank lines and anything after `#` are ignored.
+/// A `base` line, if any, has to be the first entry, since it replaces everything before it.
+///
+/// ```text
+/// base ars                # start from srs (the default), ars or classic
+/// T 0: 0,1 1,1 2,1 1,2    # cells of T in rotation 0, as col,row in the 4x4 box with row 0 at the bottom
+/// T 0>1: 0,0 -1,0 -1,1    # offsets T tries turning from rotation 0 to 1
+/// * 0>2: 0,0 0,1          # the same for every piece; half turns use the 180 kick setting unless given
+/// ```
+///
+/// Kicks copied from the base are fixed tables, so ARS's center column rule does not carry over.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct CustomRotation{
+    cells: Vec<[(isize,isize);4]>,
+    kicks: Vec<Option<Vec<(isize,isize)>>>
+}
+impl CustomRotation{
+    /// A copy of `base` to build on.
+    pub fn from_base(base: &RotationSystems) -> Self{
+        let mut cells = Vec::new();
+        let mut kicks = Vec::new();
+        for p in 0..7{
+            let piece = TetrisPieceType::from_int(p);
+            for rot in 0..4{
+                cells.push(base.cells(piece, rot));
+            }
+            for from in 0..4{
+                for to in 0..4{
+                    kicks.push(match (to+4-from)%4{
+                        1|3 => Some(base.kicks(piece, from, to, &|_,_| false)),
+                        2 if !matches!(base, RotationSystems::Srs) => Some(base.half_turn_kicks(piece, from, Kicks180::NoKicks, &|_,_| false)),
+                        _ => None
+                    });
+                }
+            }
+        }
+        CustomRotation{cells, kicks}
+    }
+    /// Parses a definition in the format described above, returning a message naming the bad line if it is malformed.
+    pub fn parse(definition: &str) -> std::result::Result<Self, String>{
+        let mut custom = CustomRotation::from_base(&RotationSystems::Srs);
+        let mut first = true;
+        for (n, line) in definition.lines().enumerate(){
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty(){
+                continue
+            }
+            let err = |msg: &str| format!("line {}: {}", n+1, msg);
+            if let Some(base) = line.strip_prefix("base "){
+                if !first{
+                    return Err(err("base has to be the first entry"))
+                }
+                first = false;
+                let base = match base.trim().to_lowercase().as_str(){
+                    "srs" => RotationSystems::Srs,
+                    "ars" => RotationSystems::Ars,
+                    "classic" => RotationSystems::Classic,
+                    _ => return Err(err("base must be srs, ars or classic"))
+                };
+                custom = CustomRotation::from_base(&base);
+                continue
+            }
+            first = false;
+            let (head, values) = line.split_once(':').ok_or_else(|| err("expected ':'"))?;
+            let (piece, states) = head.trim().split_once(' ').ok_or_else(|| err("expected a piece and a rotation"))?;
+            let pieces = match piece{
+                "*" => (0..7).map(TetrisPieceType::from_int).collect::<Vec<TetrisPieceType>>(),
+                _ => vec![(0..7).map(TetrisPieceType::from_int).find(|p| p.to_string()==piece).ok_or_else(|| err("piece must be one of IJLOSTZ or *"))?]
+            };
+            let pairs = values.split_whitespace().map(|v| {
+                let (x,y) = v.split_once(',')?;
+                Some((x.parse::<isize>().ok()?, y.parse::<isize>().ok()?))
+            }).collect::<Option<Vec<(isize,isize)>>>().ok_or_else(|| err("expected col,row pairs"))?;
+            let parse_rot = |s: &str| s.trim().parse::<usize>().ok().filter(|r| *r<4).ok_or_else(|| err("rotations are 0 to 3"));
+            match states.split_once('>'){
+                Some((from, to)) => {
+                    let (from, to) = (parse_rot(from)?, parse_rot(to)?);
+                    if from==to{
+                        return Err(err("a kick needs two different rotations"))
+                    }
+                    if pairs.is_empty(){
+                        return Err(err("a kick needs at least one offset"))
+                    }
+                    for p in pieces{
+                        custom.kicks[p.to_int() as usize*16+from*4+to]=Some(pairs.clone());
+                    }
+                }
+                None => {
+                    let rot = parse_rot(states)?;
+                    if pairs.len()!=4 || pairs.iter().any(|(c,r)| !(0..4).contains(c) || !(0..4).contains(r)){
+                        return Err(err("a rotation needs four cells inside the 4x4 box"))
+                    }
+                    let mut cells = [(0,0);4];
+                    cells.copy_from_slice(&pairs);
+                    if (1..4).any(|i| cells[..i].contains(&cells[i])){
+                        return Err(err("cells must be different"))
+                    }
+                    for p in pieces{
+                        custom.cells[p.to_int() as usize*4+rot]=cells;
+                    }
+                }
+            }
+        }
+        Ok(custom)
+    }
+    /// Checks that the tables have an entry for every piece and rotation and that cells are inside the box,
+    /// as they always are when parsed but might not be when read from elsewhere.
+    pub(crate) fn check(&self) -> std::result::Result<(), String>{
+        if self.cells.len()!=7*4 || self.kicks.len()!=7*4*4{
+            return Err(String::from("custom rotation tables are the wrong size"))
+        }
+        if self.cells.iter().flatten().any(|(c,r)| !(0..4).contains(c) || !(0..4).contains(r)){
+            return Err(String::from("custom rotation has cells outside the 4x4 box"))
+        }
+        Ok(())
+    }
+}
+impl RotationSystem for CustomRotation{
+    fn cells(&self, piece: TetrisPieceType, rot: usize) -> [(isize,isize);4]{
+        self.cells[piece.to_int() as usize*4+rot%4]
+    }
+    fn kicks(&self, piece: TetrisPieceType, from: usize, to: usize, _blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        self.kicks[piece.to_int() as usize*16+from%4*4+to%4].clone().unwrap_or_else(|| vec![(0,0)])
+    }
+    fn half_turn_kicks(&self, piece: TetrisPieceType, from: usize, kicks_180: Kicks180, _blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        self.kicks[piece.to_int() as usize*16+from%4*4+(from+2)%4].clone().unwrap_or_else(|| kicks_180.kicks(piece, from))
+    }
+}
+
+/// The rotation system picked in [`crate::Settings`].
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub enum RotationSystems{
+    Srs,
+    Ars,
+    Classic,
+    Custom(CustomRotation)
+}
+impl RotationSystems{
+    fn system(&self) -> &dyn RotationSystem{
+        match self{
+            Self::Srs => &Srs,
+            Self::Ars => &Ars,
+            Self::Classic => &Classic,
+            Self::Custom(custom) => custom
+        }
+    }
+}
+impl RotationSystem for RotationSystems{
+    fn cells(&self, piece: TetrisPieceType, rot: usize) -> [(isize,isize);4]{
+        self.system().cells(piece, rot)
+    }
+    fn kicks(&self, piece: TetrisPieceType, from: usize, to: usize, blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        self.system().kicks(piece, from, to, blocked)
+    }
+    fn half_turn_kicks(&self, piece: TetrisPieceType, from: usize, kicks_180: Kicks180, blocked: &dyn Fn(isize,isize) -> bool) -> Vec<(isize,isize)>{
+        self.system().half_turn_kicks(piece, from, kicks_180, blocked)
+    }
+}
+impl Display for RotationSystems{
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f,"{}",match &self{
+            Self::Srs => "SRS",
+            Self::Ars => "ARS",
+            Self::Classic => "Classic",
+            Self::Custom(_) => "Custom"
+        })
+    }
+}
+
+/// Kick table used for 180 degree rotations, which SRS itself does not define.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Kicks180{
+    /// The SRS+ table used by TETR.IO.
+    SrsPlus,
+    /// Only the unmoved position is tried.
+    NoKicks
+}
+impl Kicks180{
+    /// Offsets to try, in order, when rotating a half turn away from rotation `from`.
+    pub fn kicks(&self, piece: TetrisPieceType, from: usize) -> Vec<(isize,isize)>{
+        match (self, piece){
+            (Self::NoKicks, _)|(_, TetrisPieceType::O) => vec![(0,0)],
+            (Self::SrsPlus, _) => match from%4{
+                0 => vec![(0,0),(0,1),(1,1),(-1,1),(1,0),(-1,0)],
+                1 => vec![(0,0),(1,0),(1,2),(1,1),(0,2),(0,1)],
+                2 => vec![(0,0),(0,-1),(-1,-1),(1,-1),(-1,0),(1,0)],
+                3 => vec![(0,0),(-1,0),(-1,2),(-1,1),(0,2),(0,1)],
+                _ => vec![(0,0)]
+            }
+        }
+    }
+}
+impl Display for Kicks180{
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f,"{}",match &self{
+            Self::SrsPlus => "SRS+",
+            Self::NoKicks => "NoKicks"
+        })
+    }
+}
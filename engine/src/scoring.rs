@@ -0,0 +1,75 @@
+use std::fmt::{Display, Formatter, Result};
+
+/// Points for clearing 0-4 lines at once, multiplied by the level.
+const LINE_CLEAR_POINTS: [u32;5] = [0,100,300,500,800];
+/// Points for a T-spin clearing 0-3 lines, multiplied by the level.
+const T_SPIN_POINTS: [u32;4] = [400,800,1200,1600];
+const T_SPIN_MINI_POINTS: [u32;3] = [100,200,400];
+/// Bonus for a clear of 0-4 lines that leaves the board empty, multiplied by the level.
+const PERFECT_CLEAR_POINTS: [u32;5] = [0,800,1200,1800,2000];
+const BACK_TO_BACK_TETRIS_PERFECT_CLEAR_POINTS: u32 = 3200;
+/// Points per combo step past the first clear, multiplied by the level.
+const COMBO_POINTS: u32 = 50;
+pub const SOFT_DROP_POINTS: u32 = 1;
+pub const HARD_DROP_POINTS: u32 = 2;
+/// One frame at 60 fps. Gravity faster than a row per frame is treated as 20G.
+const FRAME_MS: f32 = 1000.0/60.0;
+/// The gravity curve is 20G from here on, so higher levels don't need to be computed.
+const TWENTY_G_LEVEL: u32 = 20;
+
+pub fn line_clear_score(num_cleared: u32, level: u32) -> u32{
+    LINE_CLEAR_POINTS[num_cleared.min(4) as usize]*level
+}
+
+/// How a T piece got into place, decided by the 3-corner rule when it locks.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TSpin{
+    Mini,
+    Full
+}
+impl Display for TSpin{
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f,"{}",match &self{
+            Self::Mini => "T-Spin Mini",
+            Self::Full => "T-Spin"
+        })
+    }
+}
+
+pub fn t_spin_score(kind: TSpin, num_cleared: u32, level: u32) -> u32{
+    match kind{
+        TSpin::Full => T_SPIN_POINTS[num_cleared.min(3) as usize]*level,
+        TSpin::Mini => T_SPIN_MINI_POINTS[num_cleared.min(2) as usize]*level
+    }
+}
+
+/// A difficult clear (tetris or T-spin) right after another one is worth half again as much.
+pub fn back_to_back_bonus(points: u32) -> u32{
+    points*3/2
+}
+
+/// Bonus for the `combo`th clear in a row (the first clear of a chain is combo 0).
+pub fn combo_score(combo: u32, level: u32) -> u32{
+    COMBO_POINTS*combo*level
+}
+
+/// Bonus on top of the line clear for emptying the board. `back_to_back` is whether the clear continued a back-to-back chain.
+pub fn perfect_clear_score(num_cleared: u32, back_to_back: bool, level: u32) -> u32{
+    if back_to_back && num_cleared>=4{
+        BACK_TO_BACK_TETRIS_PERFECT_CLEAR_POINTS*level
+    }else{
+        PERFECT_CLEAR_POINTS[num_cleared.min(4) as usize]*level
+    }
+}
+
+pub fn level_for_lines(lines_cleared: u32) -> u32{
+    lines_cleared/10+1
+}
+
+/// Time in milliseconds for the falling piece to move down one row at `level` (guideline gravity curve).
+/// Returns 0 for 20G, where pieces drop to the floor as soon as they spawn or move, once the curve gets faster than a row per frame.
+pub fn tick_speed(level: u32) -> u32{
+    let l = (level.clamp(1, TWENTY_G_LEVEL)-1) as f32;
+    let ms = (0.8-l*0.007).powf(l)*1000.0;
+    if ms<FRAME_MS {0} else {ms as u32}
+}
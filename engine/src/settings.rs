@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::mode::GameModes;
+use crate::randomizer::Randomizers;
+use crate::rotation::{Kicks180, RotationSystems};
+
+/// Fewest columns a board can have, so every piece fits.
+pub const MIN_BOARD_WIDTH: usize = 4;
+/// Most columns a board can have.
+pub const MAX_BOARD_WIDTH: usize = 40;
+/// Fewest visible rows a board can have.
+pub const MIN_BOARD_HEIGHT: usize = 4;
+/// Most visible rows a board can have.
+pub const MAX_BOARD_HEIGHT: usize = 60;
+/// Fewest hidden rows above the board, so pieces have room to spawn.
+pub const MIN_BUFFER_HEIGHT: usize = 2;
+/// Most hidden rows above the board.
+pub const MAX_BUFFER_HEIGHT: usize = 40;
+/// Most upcoming pieces that can be shown.
+pub const MAX_QUEUE_DISPLAY_LEN: usize = 6;
+
+/// Gameplay settings. Times are in milliseconds.
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Settings{
+    pub hold_time: u32,
+    pub hold_move_interval: u32,
+    pub down_hold_time: u32,
+    pub down_hold_move_interval: u32,
+    pub max_num_held_piece_switches: u32,
+    pub queue_display_len: usize,
+    pub lock_delay: u32,
+    pub moves_before_lock: u32,
+    /// Delay after a piece locks before the next one enters (ARE). Presses made meanwhile wait for the new piece.
+    pub entry_delay: u32,
+    /// How long full rows stay on the board before they clear and the stack falls.
+    pub line_clear_delay: u32,
+    /// Initial rotation: a rotate key held as a piece enters spawns it already turned.
+    pub irs: bool,
+    /// Initial hold: the hold key held as a piece enters holds it straight away.
+    pub ihs: bool,
+    pub randomizer: Randomizers,
+    pub rotation_system: RotationSystems,
+    pub kicks_180: Kicks180,
+    /// Columns on the board. At least 4.
+    pub board_width: usize,
+    /// Visible rows on the board.
+    pub board_height: usize,
+    /// Hidden rows above the visible ones where pieces spawn.
+    pub buffer_height: usize,
+    pub mode: GameModes,
+    /// Seed for the pieces and garbage, so a game can be played again exactly. None picks a new one every game.
+    /// Not serialized, since replays keep the seed in their header.
+    #[serde(skip)]
+    pub seed: Option<u64>
+}
+impl Default for Settings{
+    fn default() -> Settings{
+        Settings{hold_time: 150, hold_move_interval: 60, max_num_held_piece_switches: 1, queue_display_len: 4, lock_delay: 500, moves_before_lock: 15, entry_delay: 0, line_clear_delay: 0, irs: false, ihs: false, randomizer: Randomizers::RandomGenerator, rotation_system: RotationSystems::Srs,
+        down_hold_time: 50, down_hold_move_interval: 50, kicks_180: Kicks180::SrsPlus,
+        board_width: 10, board_height: 20, buffer_height: 3, mode: GameModes::Marathon{endless: false}, seed: None}
+    }
+}
+impl Settings{
+    /// Checks that the settings are within what the game supports, saying what isn't.
+    /// Settings from outside, such as a replay file, should be checked before a game is started with them,
+    /// since a board of absurd size can't even be allocated.
+    pub fn check(&self) -> std::result::Result<(), String>{
+        let ranges = [("board width", self.board_width, MIN_BOARD_WIDTH, MAX_BOARD_WIDTH), ("board height", self.board_height, MIN_BOARD_HEIGHT, MAX_BOARD_HEIGHT),
+            ("buffer height", self.buffer_height, MIN_BUFFER_HEIGHT, MAX_BUFFER_HEIGHT), ("queue length", self.queue_display_len, 0, MAX_QUEUE_DISPLAY_LEN)];
+        for (what, value, min, max) in ranges{
+            if !(min..=max).contains(&value){
+                return Err(format!("{} {} is not between {} and {}", what, value, min, max))
+            }
+        }
+        match &self.rotation_system{
+            RotationSystems::Custom(custom) => custom.check(),
+            _ => Ok(())
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Statistics over long runs of a randomizer, to check generators for fairness by the numbers rather than by feel.
+//! The `randomizer-stats` binary prints these.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::piece::TetrisPieceType;
+use crate::randomizer::Randomizers;
+use crate::rng::Rng;
+
+/// Piece counts, droughts and S/Z runs from one sample of a randomizer.
+///
+/// A drought is the number of pieces dealt between two of the same piece (or from the start to the first one).
+/// Droughts still going when the sample ends count as ending there, so a piece that never came has one as long as the sample.
+/// Arrays are indexed by [`TetrisPieceType::to_int`].
+#[derive(Clone, Debug)]
+pub struct RandomizerStats{
+    pub randomizer: Randomizers,
+    pub seed: u64,
+    pub pieces: u64,
+    pub counts: [u64;7],
+    pub max_droughts: [u64;7],
+    drought_totals: [u64;7],
+    drought_counts: [u64;7],
+    /// How many droughts of each length there were, over all pieces, indexed by length.
+    pub drought_histogram: Vec<u64>,
+    /// How many runs of S and Z pieces in a row there were, indexed by length.
+    pub snake_runs: Vec<u64>
+}
+impl RandomizerStats{
+    /// Deals `pieces` pieces from `randomizer` seeded with `seed` and tallies them up.
+    pub fn sample(randomizer: &Randomizers, seed: u64, pieces: u64) -> Self{
+        let mut rng = Rng::new(seed);
+        let mut generator = randomizer.start();
+        let mut stats = RandomizerStats{randomizer: randomizer.clone(), seed, pieces, counts: [0;7], max_droughts: [0;7], drought_totals: [0;7], drought_counts: [0;7],
+            drought_histogram: Vec::new(), snake_runs: Vec::new()};
+        let mut last_seen = [None;7];
+        let mut snake_run = 0;
+        for i in 0..pieces{
+            let piece = generator.next(&mut rng);
+            let p = piece.to_int() as usize;
+            stats.add_drought(p, last_seen[p].map_or(i, |last| i-last-1));
+            last_seen[p]=Some(i);
+            stats.counts[p]+=1;
+            if matches!(piece, TetrisPieceType::S | TetrisPieceType::Z){
+                snake_run+=1;
+            }else if snake_run>0{
+                add_to(&mut stats.snake_runs, snake_run);
+                snake_run=0;
+            }
+        }
+        if snake_run>0{
+            add_to(&mut stats.snake_runs, snake_run);
+        }
+        for (p, last) in last_seen.into_iter().enumerate(){
+            // the piece dealt last has no drought going
+            if last!=Some(pieces-1) && pieces>0{
+                stats.add_drought(p, last.map_or(pieces, |last| pieces-last-1));
+            }
+        }
+        stats
+    }
+    fn add_drought(&mut self, p: usize, drought: u64){
+        self.drought_totals[p]+=drought;
+        self.drought_counts[p]+=1;
+        self.max_droughts[p]=self.max_droughts[p].max(drought);
+        add_to(&mut self.drought_histogram, drought as usize);
+    }
+    /// Share of the pieces that were `piece`, from 0 to 1.
+    pub fn frequency(&self, piece: TetrisPieceType) -> f64{
+        self.counts[piece.to_int() as usize] as f64/self.pieces.max(1) as f64
+    }
+    pub fn max_drought(&self, piece: TetrisPieceType) -> u64{
+        self.max_droughts[piece.to_int() as usize]
+    }
+    pub fn mean_drought(&self, piece: TetrisPieceType) -> f64{
+        let p = piece.to_int() as usize;
+        self.drought_totals[p] as f64/self.drought_counts[p].max(1) as f64
+    }
+    /// Most S and Z pieces that came in a row.
+    pub fn longest_snake_run(&self) -> usize{
+        self.snake_runs.len().saturating_sub(1)
+    }
+
+    /// A readable summary with a bar chart of drought lengths.
+    pub fn to_text(&self) -> String{
+        let mut out = format!("{} pieces from {} with seed {}\n\n", self.pieces, self.randomizer, self.seed);
+        let _ = writeln!(out, "{:<5}  {:>9}  {:>8}  {:>12}  {:>11}", "piece", "count", "freq", "mean drought", "max drought");
+        for piece in pieces(){
+            let _ = writeln!(out, "{:<5}  {:>9}  {:>8.4}  {:>12.3}  {:>11}", piece.to_string(), self.counts[piece.to_int() as usize], self.frequency(piece), self.mean_drought(piece), self.max_drought(piece));
+        }
+        let _ = writeln!(out, "\nlongest S/Z run: {}\nS/Z runs by length:", self.longest_snake_run());
+        for (len, runs) in self.snake_runs.iter().enumerate().skip(1){
+            let _ = writeln!(out, "{:>4}  {}", len, runs);
+        }
+        let _ = writeln!(out, "\ndroughts by length:");
+        let most = self.drought_histogram.iter().copied().max().unwrap_or(0).max(1);
+        for (len, n) in self.drought_histogram.iter().enumerate(){
+            let _ = writeln!(out, "{:>4}  {:<50}  {}", len, "#".repeat((n*50).div_ceil(most) as usize), n);
+        }
+        out
+    }
+    /// Three tables separated by blank lines: per piece stats, the drought histogram and S/Z runs.
+    pub fn to_csv(&self) -> String{
+        let mut out = String::from("piece,count,frequency,mean_drought,max_drought\n");
+        for piece in pieces(){
+            let _ = writeln!(out, "{},{},{},{},{}", piece, self.counts[piece.to_int() as usize], self.frequency(piece), self.mean_drought(piece), self.max_drought(piece));
+        }
+        out.push_str("\ndrought,count\n");
+        for (len, n) in self.drought_histogram.iter().enumerate(){
+            let _ = writeln!(out, "{},{}", len, n);
+        }
+        out.push_str("\nsnake_run,count\n");
+        for (len, n) in self.snake_runs.iter().enumerate().skip(1){
+            let _ = writeln!(out, "{},{}", len, n);
+        }
+        out
+    }
+    /// One JSON object, with the per piece stats keyed by piece name.
+    pub fn to_json(&self) -> String{
+        let per_piece = pieces().map(|piece| (piece.to_string(), PieceJson{count: self.counts[piece.to_int() as usize], frequency: self.frequency(piece),
+            mean_drought: self.mean_drought(piece), max_drought: self.max_drought(piece)})).collect();
+        serde_json::to_string(&StatsJson{randomizer: self.randomizer.to_string(), seed: self.seed, pieces: self.pieces, per_piece,
+            longest_snake_run: self.longest_snake_run(), snake_runs: &self.snake_runs, drought_histogram: &self.drought_histogram}).expect("stats always serialize")
+    }
+}
+
+/// Layout of [`RandomizerStats::to_json`].
+#[derive(Serialize)]
+struct StatsJson<'a>{
+    randomizer: String,
+    seed: u64,
+    pieces: u64,
+    per_piece: BTreeMap<String, PieceJson>,
+    longest_snake_run: usize,
+    snake_runs: &'a [u64],
+    drought_histogram: &'a [u64]
+}
+#[derive(Serialize)]
+struct PieceJson{
+    count: u64,
+    frequency: f64,
+    mean_drought: f64,
+    max_drought: u64
+}
+
+fn pieces() -> impl Iterator<Item = TetrisPieceType>{
+    (0..7).map(TetrisPieceType::from_int)
+}
+
+fn add_to(histogram: &mut Vec<u64>, len: usize){
+    if histogram.len()<=len{
+        histogram.resize(len+1, 0);
+    }
+    histogram[len]+=1;
+}
@@ -0,0 +1,128 @@
+use tetris_engine::{Action, Game, Input, RotationSystems, Settings, TetrisBoard, TetrisPieceType};
+
+const PIECES: [TetrisPieceType;7] = [TetrisPieceType::I, TetrisPieceType::J, TetrisPieceType::L, TetrisPieceType::O, TetrisPieceType::S, TetrisPieceType::T, TetrisPieceType::Z];
+
+/// (width, visible height, buffer height)
+const SIZES: [(usize,usize,usize);6] = [(4,20,3), (10,20,3), (20,20,3), (10,40,3), (12,16,20), (5,6,2)];
+
+fn columns(board: &TetrisBoard) -> (isize,isize){
+    let cols = board.falling_cells().map(|c| c.0);
+    (*cols.iter().min().unwrap(), *cols.iter().max().unwrap())
+}
+
+#[test]
+fn pieces_spawn_centered_above_the_visible_rows(){
+    for (w,h,buffer) in SIZES{
+        for piece in PIECES{
+            let board = TetrisBoard::new(w,h,buffer,piece,RotationSystems::Srs);
+            assert_eq!(board.dimentions(), (w as isize, (h+buffer) as isize));
+            assert_eq!(board.falling_cells().iter().map(|c| c.1).min(), Some(h as isize), "{}x{}+{} {}", w, h, buffer, piece);
+            let (left, right) = columns(&board);
+            assert!((left-(w as isize-1-right)).abs()<=1, "{}x{}+{} {} spawned off center", w, h, buffer, piece);
+        }
+    }
+}
+
+#[test]
+fn short_buffers_spawn_as_high_as_they_can(){
+    for piece in PIECES{
+        let board = TetrisBoard::new(10,20,1,piece,RotationSystems::Srs);
+        let rows = board.falling_cells().map(|c| c.1);
+        assert_eq!(rows.iter().max(), Some(&20), "{}", piece);
+    }
+}
+
+#[test]
+fn pieces_reach_both_walls_and_the_floor(){
+    for (w,h,buffer) in SIZES{
+        for piece in PIECES{
+            for rot in 0..4{
+                let mut board = TetrisBoard::new(w,h,buffer,piece,RotationSystems::Srs);
+                assert!(board.place_falling_piece(piece, rot, (w as isize-4)/2, 1));
+                while board.move_left(){}
+                assert_eq!(columns(&board).0, 0, "{}x{} {} rot {}", w, h, piece, rot);
+                while board.move_right(){}
+                assert_eq!(columns(&board).1, w as isize-1, "{}x{} {} rot {}", w, h, piece, rot);
+                while board.move_down(){}
+                assert_eq!(board.falling_cells().iter().map(|c| c.1).min(), Some(0));
+            }
+        }
+    }
+}
+
+#[test]
+fn rotation_kicks_off_the_walls(){
+    for (w,h,buffer) in SIZES{
+        for piece in PIECES{
+            let mut board = TetrisBoard::new(w,h,buffer,piece,RotationSystems::Srs);
+            for _ in 0..4{ board.move_down(); }
+            assert!(board.rotate_clockwise());
+            while board.move_right(){}
+            // turning back to flat at the right wall has to kick left
+            assert!(board.rotate_counter_clockwise(), "{}x{} {}", w, h, piece);
+            assert!(columns(&board).1<w as isize);
+            while board.move_left(){}
+            assert!(board.rotate_counter_clockwise(), "{}x{} {}", w, h, piece);
+            assert!(columns(&board).0>=0);
+        }
+    }
+}
+
+#[test]
+fn full_rows_clear_at_any_width(){
+    for (w,h,buffer) in SIZES{
+        let mut board = TetrisBoard::new(w,h,buffer,TetrisPieceType::I,RotationSystems::Srs);
+        let right = w as isize-1;
+        for c in 4..w as isize{
+            board.set_tile(c, 0, Some(TetrisPieceType::O));
+        }
+        // something above the row to fall into it, out of the I's way
+        if right>=4{
+            board.set_tile(right, 1, Some(TetrisPieceType::Z));
+        }
+        assert!(board.place_falling_piece(TetrisPieceType::I, 0, 0, 2));
+        board.drop();
+        assert_eq!(board.clear_lines(), 1, "{}x{}", w, h);
+        if right>=4{
+            assert_eq!(board.tiles()[right as usize], Some(TetrisPieceType::Z));
+            assert_eq!(board.tiles().iter().filter(|t| t.is_some()).count(), 1);
+        }else{
+            assert!(board.is_empty());
+        }
+    }
+}
+
+#[test]
+fn games_use_the_board_size_from_settings(){
+    for (w,h,buffer) in SIZES{
+        let settings = Settings{board_width: w, board_height: h, buffer_height: buffer, ..Settings::default()};
+        let mut game = Game::new(settings);
+        assert_eq!(game.board().dimentions(), (w as isize, (h+buffer) as isize));
+        assert_eq!(game.board().visible_height(), h as isize);
+        // stacking in the middle has to top out eventually
+        let mut pieces = 0;
+        while !game.is_over(){
+            game.step(1, &[Input::Press(Action::HardDrop)]);
+            pieces+=1;
+            assert!(pieces<(w*(h+buffer)) as u32, "{}x{}+{} never topped out", w, h, buffer);
+        }
+    }
+}
+
+#[test]
+fn boxes_can_hang_off_the_edges_but_cells_cannot(){
+    for (w,h,buffer) in SIZES{
+        let w = w as isize;
+        let mut board = TetrisBoard::new(w as usize,h,buffer,TetrisPieceType::I,RotationSystems::Srs);
+        // the vertical I sits in the third column of its box
+        assert!(board.place_falling_piece(TetrisPieceType::I, 1, -2, 0));
+        assert_eq!(columns(&board), (0,0));
+        assert!(!board.move_left());
+        assert!(!board.place_falling_piece(TetrisPieceType::I, 1, -3, 0));
+        assert!(board.place_falling_piece(TetrisPieceType::I, 1, w-3, 0));
+        assert_eq!(columns(&board), (w-1,w-1));
+        assert!(!board.move_right());
+        assert!(!board.place_falling_piece(TetrisPieceType::I, 1, w-2, 0), "{}x{} wrapped to the left wall", w, h);
+        assert!(!board.place_falling_piece(TetrisPieceType::I, 0, 0, -3));
+    }
+}
@@ -0,0 +1,235 @@
+use tetris_engine::{Action, CustomRotation, Game, GameEvent, Input, RotationSystems, Settings, TetrisBoard, TetrisPieceType};
+
+fn press(game: &mut Game, elapsed_ms: u32, action: Action) -> Vec<GameEvent>{
+    game.step(elapsed_ms, &[Input::Press(action)])
+}
+
+fn tap(game: &mut Game, elapsed_ms: u32, action: Action) -> Vec<GameEvent>{
+    game.step(elapsed_ms, &[Input::Press(action), Input::Release(action)])
+}
+
+/// Column and row of every tile currently covered by the falling piece.
+fn falling_cells(game: &Game) -> Vec<(isize,isize)>{
+    let (w,h) = game.board().dimentions();
+    (0..h).flat_map(|r| (0..w).map(move |c| (c,r))).filter(|(c,r)| game.board().check_loc_for_falling_piece(*c, *r)).collect()
+}
+
+/// Settings where soft drop goes straight to the floor, so tests can ground a piece without waiting on gravity.
+fn sonic_drop_settings() -> Settings{
+    Settings{down_hold_time: 0, down_hold_move_interval: 0, ..Settings::default()}
+}
+
+/// Settings where every piece is a flat 4-wide bar on a 4-wide board, so each hard drop clears a line.
+fn line_per_piece_settings() -> Settings{
+    let bars = CustomRotation::parse("* 0: 0,0 1,0 2,0 3,0").unwrap();
+    Settings{board_width: 4, rotation_system: RotationSystems::Custom(bars), ..Settings::default()}
+}
+
+#[test]
+fn hard_dropping_in_place_eventually_tops_out(){
+    let mut game = Game::new(Settings::default());
+    let mut drops = 0;
+    while !game.is_over(){
+        let events = press(&mut game, 0, Action::HardDrop);
+        assert!(events.contains(&GameEvent::PieceLocked{lines_cleared: 0}));
+        drops+=1;
+        assert!(drops<100, "stacking pieces in the middle never ended the game");
+    }
+    assert!(game.score()>0);
+    assert!(press(&mut game, 0, Action::HardDrop).is_empty());
+}
+
+#[test]
+fn soft_drop_scores_one_point_per_row(){
+    let mut game = Game::new(Settings::default());
+    tap(&mut game, 0, Action::SoftDrop);
+    tap(&mut game, 0, Action::SoftDrop);
+    assert_eq!(game.score(), 2);
+}
+
+#[test]
+fn hold_is_limited_per_piece(){
+    let mut game = Game::new(Settings::default());
+    let first = game.board().falling_piece();
+    let next = game.piece_queue()[0];
+    press(&mut game, 0, Action::Hold);
+    assert_eq!(game.held_piece(), Some(first));
+    assert_eq!(game.board().falling_piece(), next);
+    press(&mut game, 0, Action::Hold);
+    assert_eq!(game.held_piece(), Some(first));
+    assert_eq!(game.board().falling_piece(), next);
+}
+
+#[test]
+fn queue_always_covers_the_display(){
+    let settings = Settings{queue_display_len: 6, ..Settings::default()};
+    let mut game = Game::new(settings);
+    for _ in 0..20{
+        assert!(game.piece_queue().len()>=6);
+        press(&mut game, 0, Action::HardDrop);
+        if game.is_over(){
+            game.restart();
+        }
+    }
+}
+
+#[test]
+fn gravity_moves_one_row_per_tick(){
+    let mut game = Game::new(Settings::default());
+    let start = falling_cells(&game);
+    game.step(game.tick_speed()-1, &[]);
+    assert_eq!(falling_cells(&game), start);
+    game.step(1, &[]);
+    assert_eq!(falling_cells(&game), start.iter().map(|(c,r)| (*c,r-1)).collect::<Vec<(isize,isize)>>());
+    // splitting the same time into small steps gives the same result
+    let mut split = Game::new(Settings::default());
+    for _ in 0..game.tick_speed(){
+        split.step(1, &[]);
+    }
+    assert_eq!(falling_cells(&split).len(), 4);
+    assert_eq!(split.elapsed_ms(), game.elapsed_ms());
+}
+
+#[test]
+fn piece_locks_after_lock_delay(){
+    let mut game = Game::new(sonic_drop_settings());
+    press(&mut game, 0, Action::SoftDrop);
+    let lock_delay = game.settings().lock_delay;
+    assert!(game.step(lock_delay-1, &[]).is_empty());
+    assert_eq!(game.step(1, &[]), vec![GameEvent::PieceLocked{lines_cleared: 0}]);
+}
+
+#[test]
+fn moves_reset_lock_delay_until_moves_before_lock_runs_out(){
+    let settings = Settings{moves_before_lock: 3, lock_delay: 500, ..sonic_drop_settings()};
+    let mut game = Game::new(settings);
+    press(&mut game, 0, Action::SoftDrop);
+    // three resets are allowed, each pushing the lock back by a full lock delay
+    assert!(tap(&mut game, 400, Action::Left).is_empty());
+    assert!(tap(&mut game, 400, Action::Right).is_empty());
+    assert!(tap(&mut game, 400, Action::Left).is_empty());
+    // the fourth move no longer resets, so the piece locks 500ms after the third
+    assert!(tap(&mut game, 400, Action::Right).is_empty());
+    assert!(game.step(99, &[]).is_empty());
+    assert_eq!(game.step(1, &[]), vec![GameEvent::PieceLocked{lines_cleared: 0}]);
+}
+
+#[test]
+fn held_direction_auto_repeats_after_delay(){
+    let settings = Settings{hold_time: 150, hold_move_interval: 60, ..Settings::default()};
+    let mut game = Game::new(settings);
+    let column = |game: &Game| falling_cells(game).iter().map(|c| c.0).min().unwrap();
+    let start = column(&game);
+    press(&mut game, 0, Action::Left);
+    assert_eq!(column(&game), start-1);
+    game.step(149, &[]);
+    assert_eq!(column(&game), start-1);
+    game.step(1, &[]);
+    assert_eq!(column(&game), start-2);
+    game.step(60, &[]);
+    assert_eq!(column(&game), start-3);
+    game.step(1000, &[Input::Release(Action::Left)]);
+    assert_eq!(column(&game), 0);
+    game.step(1000, &[]);
+    assert_eq!(column(&game), 0);
+}
+
+#[test]
+fn entry_delay_holds_back_the_next_piece(){
+    let mut game = Game::new(Settings{entry_delay: 200, ..Settings::default()});
+    press(&mut game, 0, Action::HardDrop);
+    assert!(falling_cells(&game).is_empty());
+    game.step(199, &[]);
+    assert!(falling_cells(&game).is_empty());
+    game.step(1, &[]);
+    assert_eq!(falling_cells(&game).len(), 4);
+}
+
+#[test]
+fn presses_during_entry_delay_apply_to_the_next_piece(){
+    let settings = Settings{entry_delay: 200, hold_time: 100, hold_move_interval: 0, ..Settings::default()};
+    let mut game = Game::new(settings);
+    press(&mut game, 0, Action::HardDrop);
+    let next = game.piece_queue()[0];
+    assert!(press(&mut game, 50, Action::Hold).is_empty());
+    assert_eq!(game.held_piece(), None);
+    // auto-repeat charges while waiting, so the new piece goes straight to the wall
+    press(&mut game, 0, Action::Left);
+    game.step(150, &[]);
+    assert_eq!(game.held_piece(), Some(next));
+    assert_eq!(falling_cells(&game).iter().map(|c| c.0).min(), Some(0));
+}
+
+#[test]
+fn line_clear_delay_leaves_full_rows_on_the_board(){
+    let mut game = Game::new(Settings{line_clear_delay: 300, ..line_per_piece_settings()});
+    assert!(press(&mut game, 0, Action::HardDrop).contains(&GameEvent::PieceLocked{lines_cleared: 1}));
+    assert!(game.is_clearing_lines());
+    assert_eq!(game.board().full_rows(), vec![0]);
+    assert_eq!(game.lines_cleared(), 1);
+    game.step(299, &[]);
+    assert_eq!(game.board().full_rows(), vec![0]);
+    assert!(falling_cells(&game).is_empty());
+    game.step(1, &[]);
+    assert!(!game.is_clearing_lines());
+    assert!(game.board().is_empty());
+    assert_eq!(falling_cells(&game).len(), 4);
+}
+
+#[test]
+fn a_buffered_hard_drop_waits_out_the_next_delay_too(){
+    let mut game = Game::new(Settings{line_clear_delay: 100, entry_delay: 100, ..line_per_piece_settings()});
+    press(&mut game, 0, Action::HardDrop);
+    game.step(50, &[Input::Press(Action::HardDrop), Input::Release(Action::HardDrop), Input::Press(Action::HardDrop)]);
+    // the first buffered drop locks the new piece as soon as it enters, the second waits for the one after
+    assert!(game.step(150, &[]).contains(&GameEvent::PieceLocked{lines_cleared: 1}));
+    assert!(falling_cells(&game).is_empty());
+    assert!(game.step(200, &[]).contains(&GameEvent::PieceLocked{lines_cleared: 1}));
+    assert_eq!(game.lines_cleared(), 3);
+}
+
+#[test]
+fn irs_spawns_the_next_piece_turned_while_a_rotate_key_is_held(){
+    let mut game = Game::new(Settings{irs: true, entry_delay: 100, ..Settings::default()});
+    press(&mut game, 0, Action::HardDrop);
+    press(&mut game, 50, Action::RotateCounterClockwise);
+    game.step(50, &[]);
+    assert_eq!(game.board().falling_rot(), 3);
+    // the press was used up spawning, not saved for later
+    game.step(0, &[Input::Release(Action::RotateCounterClockwise)]);
+    assert_eq!(game.board().falling_rot(), 3);
+    press(&mut game, 0, Action::HardDrop);
+    game.step(100, &[]);
+    assert_eq!(game.board().falling_rot(), 0);
+}
+
+#[test]
+fn ihs_holds_the_next_piece_as_it_enters(){
+    let mut game = Game::new(Settings{ihs: true, entry_delay: 100, ..Settings::default()});
+    press(&mut game, 0, Action::HardDrop);
+    let next = game.piece_queue()[0];
+    let after = game.piece_queue()[1];
+    press(&mut game, 50, Action::Hold);
+    game.step(50, &[]);
+    assert_eq!(game.held_piece(), Some(next));
+    assert_eq!(game.board().falling_piece(), after);
+    // that was this piece's hold
+    game.step(0, &[Input::Release(Action::Hold), Input::Press(Action::Hold)]);
+    assert_eq!(game.held_piece(), Some(next));
+}
+
+#[test]
+fn irs_can_rescue_a_blocked_spawn(){
+    let mut board = TetrisBoard::make(10, 20, TetrisPieceType::I);
+    // the flat I spawns in row 20 across columns 3 to 6; block it there and one row up
+    board.set_tile(3, 20, Some(TetrisPieceType::O));
+    board.set_tile(3, 21, Some(TetrisPieceType::O));
+    assert!(!board.new_falling_piece(TetrisPieceType::I));
+    assert!(board.new_falling_piece_rotated(TetrisPieceType::I, 1));
+    assert_eq!(board.falling_rot(), 1);
+    // a turn that doesn't fit falls back to the spawn rotation
+    let mut open = TetrisBoard::make(10, 20, TetrisPieceType::I);
+    open.set_tile(5, 19, Some(TetrisPieceType::O));
+    assert!(open.new_falling_piece_rotated(TetrisPieceType::I, 1));
+    assert_eq!(open.falling_rot(), 0);
+}
@@ -0,0 +1,110 @@
+use tetris_engine::garbage::garbage_holes;
+use tetris_engine::{Game, GameModes, Rng, Settings, TetrisBoard, TetrisPieceType};
+
+fn filled(board: &TetrisBoard, col: isize, row: isize) -> bool{
+    board.is_garbage(col, row) || board.tiles()[(row*board.dimentions().0+col) as usize].is_some()
+}
+
+#[test]
+fn garbage_pushes_the_stack_up(){
+    let mut board = TetrisBoard::make(10,20,TetrisPieceType::T);
+    board.set_tile(4, 0, Some(TetrisPieceType::O));
+    assert!(board.insert_garbage(&[2, 7]));
+    // bottom row first
+    for c in 0..10{
+        assert_eq!(board.is_garbage(c, 0), c!=2);
+        assert_eq!(board.is_garbage(c, 1), c!=7);
+        assert!(!board.is_garbage(c, 2));
+    }
+    assert_eq!(board.tiles()[2*10+4], Some(TetrisPieceType::O));
+    assert_eq!(board.tiles().iter().filter(|t| t.is_some()).count(), 1);
+    assert_eq!(board.garbage_rows(), 2);
+}
+
+#[test]
+fn garbage_clears_like_any_other_row(){
+    let mut board = TetrisBoard::make(10,20,TetrisPieceType::I);
+    assert!(board.insert_garbage(&[5, 0, 0, 0, 0]));
+    assert!(board.place_falling_piece(TetrisPieceType::I, 1, -2, 10));
+    board.drop();
+    assert_eq!(board.clear_lines(), 4);
+    assert_eq!(board.garbage_cleared(), 4);
+    assert_eq!(board.garbage_rows(), 1);
+    assert!((0..10).all(|c| board.is_garbage(c, 0)==(c!=5)));
+    assert!(!filled(&board, 0, 1));
+}
+
+#[test]
+fn garbage_lifts_the_falling_piece_out_of_the_way(){
+    let mut board = TetrisBoard::make(10,20,TetrisPieceType::O);
+    while board.move_down(){}
+    let rows = board.falling_cells().map(|c| c.1);
+    assert!(board.insert_garbage(&[9]));
+    assert_eq!(board.falling_cells().map(|c| c.1), rows.map(|r| r+1));
+}
+
+#[test]
+fn pushing_tiles_out_the_top_is_a_top_out(){
+    let mut board = TetrisBoard::new(10,4,2,TetrisPieceType::I,tetris_engine::RotationSystems::Srs);
+    assert!(board.insert_garbage(&[0, 0, 0, 0]));
+    assert!(board.insert_garbage(&[0]), "the piece still has room in the buffer");
+    assert!(!board.insert_garbage(&[0]));
+}
+
+#[test]
+fn messiness_decides_how_often_holes_move(){
+    let mut rng = Rng::new(1);
+    let straight = garbage_holes(&mut rng, 50, 10, 0, Some(3));
+    assert!(straight.iter().all(|h| *h==3));
+    let messy = garbage_holes(&mut rng, 50, 10, 100, Some(3));
+    assert_eq!(messy.len(), 50);
+    assert!(messy.iter().all(|h| (0..10).contains(h)));
+    // bottom row first, so each hole differs from the one after it, and the top one from the row above
+    assert!(messy.windows(2).all(|w| w[0]!=w[1]));
+    assert_ne!(messy[49], 3);
+}
+
+#[test]
+fn dig_starts_with_garbage_and_counts_what_is_left(){
+    let game = Game::new(Settings{mode: GameModes::Dig{lines: 18, messiness: 50}, ..Settings::default()});
+    assert_eq!(game.board().garbage_rows(), 10);
+    assert_eq!(game.garbage_remaining(), 18);
+    for r in 0..10{
+        assert_eq!((0..10).filter(|c| !game.board().is_garbage(*c, r)).count(), 1, "row {} should have one hole", r);
+    }
+    let short = Game::new(Settings{mode: GameModes::Dig{lines: 4, messiness: 50}, board_height: 6, ..Settings::default()});
+    assert_eq!(short.board().garbage_rows(), 3);
+    assert!(GameModes::Dig{lines: 10, messiness: 0}.is_complete(0, 10, 0));
+    assert!(!GameModes::Dig{lines: 10, messiness: 0}.is_complete(40, 9, 0));
+}
+
+#[test]
+fn survival_garbage_rises_faster_and_faster(){
+    let survival = GameModes::Survival{interval_ms: 10_000};
+    assert_eq!(survival.garbage_interval(0), Some(10_000));
+    assert_eq!(survival.garbage_interval(1), Some(9_500));
+    let intervals = (0..100).map(|r| survival.garbage_interval(r).unwrap()).collect::<Vec<u32>>();
+    assert!(intervals.windows(2).all(|w| w[1]<=w[0]));
+    assert_eq!(survival.garbage_interval(u32::MAX), Some(tetris_engine::mode::SURVIVAL_MIN_INTERVAL_MS));
+    assert_eq!(GameModes::Zen.garbage_interval(3), None);
+}
+
+#[test]
+fn survival_pushes_garbage_under_the_falling_piece(){
+    // no gravity to speak of and no lock delay, so only garbage moves things
+    let settings = Settings{mode: GameModes::Survival{interval_ms: 10_000}, lock_delay: u32::MAX, ..Settings::default()};
+    let mut game = Game::new(settings);
+    assert_eq!(game.board().garbage_rows(), 0);
+    game.step(9_999, &[]);
+    assert_eq!(game.board().garbage_rows(), 0);
+    assert_eq!(game.next_garbage_in(), Some(1));
+    game.step(1, &[]);
+    assert_eq!(game.board().garbage_rows(), 1);
+    assert_eq!(game.next_garbage_in(), Some(9_500));
+    game.step(9_500, &[]);
+    assert_eq!(game.board().garbage_rows(), 2);
+    // eventually it pushes the stack out the top
+    game.step(1_000_000, &[]);
+    assert!(game.is_over());
+    assert!(!game.is_finished());
+}
@@ -0,0 +1,107 @@
+use tetris_engine::master::{self, Grade, Timing};
+use tetris_engine::{Action, Game, GameEvent, GameModes, Input, Settings, TetrisBoard, TetrisPieceType};
+
+fn master_game() -> Game{
+    Game::new(Settings{mode: GameModes::Master, ..Settings::default()})
+}
+
+#[test]
+fn gravity_reaches_20g_at_level_500(){
+    assert_eq!(master::tick_speed(0), 1066);
+    assert!(master::tick_speed(170)<master::tick_speed(100));
+    // the famous slowdown at 200
+    assert_eq!(master::tick_speed(200), master::tick_speed(0));
+    assert_eq!(master::tick_speed(251), 16, "one row a frame");
+    assert!(master::tick_speed(499)>0);
+    assert_eq!(master::tick_speed(500), 0);
+    assert_eq!(master::tick_speed(999), 0);
+}
+
+#[test]
+fn delays_shrink_each_section_after_500(){
+    assert_eq!(Timing::for_level(0), Timing::for_level(499));
+    assert_eq!(Timing::for_level(0).are_ms(), 416);
+    assert_eq!(Timing::for_level(0).line_clear_ms(), 666);
+    let mut last = Timing::for_level(0);
+    for level in (500..1000).step_by(100){
+        let t = Timing::for_level(level);
+        assert!(t.line_clear<last.line_clear || t.lock<last.lock, "level {}", level);
+        last=t;
+    }
+    assert_eq!(Timing::for_level(999).lock_ms(), 283);
+}
+
+#[test]
+fn pieces_stop_at_the_end_of_each_section(){
+    assert_eq!(master::level_after_piece(0), 1);
+    assert_eq!(master::level_after_piece(98), 99);
+    assert_eq!(master::level_after_piece(99), 99);
+    assert_eq!(master::level_after_piece(199), 199);
+    assert_eq!(master::level_after_piece(998), 998);
+    assert_eq!(master::level_after_clear(99, 1), 100);
+    assert_eq!(master::level_after_clear(997, 4), 999);
+}
+
+#[test]
+fn grades_go_from_9_to_gm(){
+    assert_eq!(Grade::for_score(0).to_string(), "9");
+    assert_eq!(Grade::for_score(399).to_string(), "9");
+    assert_eq!(Grade::for_score(400).to_string(), "8");
+    assert_eq!(Grade::for_score(12000).to_string(), "1");
+    assert_eq!(Grade::for_score(16000).to_string(), "S1");
+    assert_eq!(Grade::for_score(u32::MAX).to_string(), "S9");
+    assert_eq!(Grade::GRAND_MASTER.to_string(), "GM");
+    assert!(Grade::for_score(u32::MAX)<Grade::GRAND_MASTER);
+    assert_eq!(Grade::from_rank(Grade::for_score(16000).rank()), Grade::for_score(16000));
+}
+
+#[test]
+fn clears_score_the_tgm_way(){
+    assert_eq!(master::clear_score(0, 1, 0, 1, false), 1);
+    // a tetris at 100 after another clear, with a few rows of soft drop
+    let combo = master::next_combo(master::next_combo(1, 2), 4);
+    assert_eq!(combo, 9);
+    assert_eq!(master::clear_score(100, 4, 2, combo, false), (26+2)*4*9);
+    assert_eq!(master::clear_score(100, 4, 2, combo, true), (26+2)*4*9*4);
+    assert_eq!(master::next_combo(combo, 0), 1);
+}
+
+#[test]
+fn the_next_piece_enters_after_the_entry_delay(){
+    let mut game = master_game();
+    let are = Timing::for_level(0).are_ms();
+    let events = game.step(0, &[Input::Press(Action::HardDrop)]);
+    assert_eq!(events, vec![GameEvent::PieceLocked{lines_cleared: 0}]);
+    assert!(!game.board().has_falling_piece());
+    game.step(are-1, &[]);
+    assert!(!game.board().has_falling_piece());
+    assert_eq!(game.master_level(), 0);
+    game.step(1, &[]);
+    assert!(game.board().has_falling_piece());
+    assert_eq!(game.master_level(), 1);
+    assert_eq!(game.pieces_placed(), 1);
+}
+
+#[test]
+fn master_gravity_follows_the_internal_level(){
+    let game = master_game();
+    assert_eq!(game.tick_speed(), master::tick_speed(0));
+    assert_eq!(game.grade().to_string(), "9");
+    assert!(game.section_times().is_empty());
+    assert_eq!(GameModes::Master.to_string(), "Master");
+}
+
+#[test]
+fn full_rows_wait_on_the_board_until_cleared(){
+    let mut board = TetrisBoard::make(4, 6, TetrisPieceType::I);
+    assert!(board.full_rows().is_empty());
+    board.drop();
+    assert!(!board.has_falling_piece());
+    assert_eq!(board.full_rows(), vec![0]);
+    assert!(board.clears_everything());
+    board.set_tile(0, 1, Some(TetrisPieceType::O));
+    assert!(!board.clears_everything());
+    board.set_tile(0, 1, None);
+    assert_eq!(board.clear_lines(), 1);
+    assert!(board.is_empty());
+}
@@ -0,0 +1,151 @@
+use tetris_engine::{Action, Game, GameEvent, GameModes, Input, Settings};
+
+#[test]
+fn sprint_is_complete_at_its_line_target(){
+    for lines in [20, 40, 100]{
+        let sprint = GameModes::Sprint{lines};
+        assert!(!sprint.is_complete(lines-1, 0, 0));
+        assert!(sprint.is_complete(lines, 0, 0));
+        assert!(sprint.is_complete(lines+3, 0, 0), "a triple past the target still finishes");
+    }
+    assert!(!GameModes::Marathon{endless: true}.is_complete(10_000, 10_000, u64::MAX));
+}
+
+#[test]
+fn topping_out_in_sprint_is_not_finishing(){
+    let mut game = Game::new(Settings{mode: GameModes::Sprint{lines: 40}, ..Settings::default()});
+    let mut events = Vec::new();
+    while !game.is_over(){
+        events.extend(game.step(10, &[Input::Press(Action::HardDrop)]));
+    }
+    assert!(!game.is_finished());
+    assert!(events.contains(&GameEvent::GameOver));
+    assert!(!events.contains(&GameEvent::Finished));
+    assert!(game.splits().is_empty());
+}
+
+#[test]
+fn the_timer_stops_when_the_game_ends(){
+    let mut game = Game::new(Settings::default());
+    while !game.is_over(){
+        game.step(10, &[Input::Press(Action::HardDrop)]);
+    }
+    let end = game.elapsed_ms();
+    game.step(1000, &[]);
+    assert_eq!(game.elapsed_ms(), end);
+}
+
+#[test]
+fn ultra_ends_exactly_when_time_runs_out(){
+    // no gravity to speak of, so the game can't top out before time is up
+    let settings = Settings{mode: GameModes::Ultra{duration_ms: 120_000}, lock_delay: u32::MAX, ..Settings::default()};
+    let mut game = Game::new(settings.clone());
+    game.step(119_999, &[]);
+    assert!(!game.is_over());
+    let events = game.step(5_000, &[Input::Press(Action::HardDrop)]);
+    assert_eq!(events, vec![GameEvent::Finished]);
+    assert!(game.is_finished());
+    assert_eq!(game.elapsed_ms(), 120_000);
+    // one long step lands on the limit too
+    let mut long = Game::new(settings);
+    long.step(1_000_000, &[]);
+    assert!(long.is_finished());
+    assert_eq!(long.elapsed_ms(), 120_000);
+}
+
+#[test]
+fn ultra_is_complete_once_its_time_is_up(){
+    let ultra = GameModes::Ultra{duration_ms: 120_000};
+    assert_eq!(ultra.time_limit(), Some(120_000));
+    assert!(!ultra.is_complete(1000, 0, 119_999));
+    assert!(ultra.is_complete(0, 0, 120_000));
+    assert_eq!(GameModes::Sprint{lines: 40}.time_limit(), None);
+}
+
+#[test]
+fn modes_display_their_goal(){
+    assert_eq!(GameModes::Marathon{endless: false}.to_string(), "Marathon");
+    assert_eq!(GameModes::Marathon{endless: true}.to_string(), "Marathon (endless)");
+    assert_eq!(GameModes::Sprint{lines: 40}.to_string(), "Sprint 40L");
+    assert_eq!(GameModes::Ultra{duration_ms: 120_000}.to_string(), "Ultra 120s");
+}
+
+#[test]
+fn marathon_ends_after_level_15_unless_endless(){
+    let marathon = GameModes::Marathon{endless: false};
+    assert!(!marathon.is_complete(149, 0, u64::MAX));
+    assert!(marathon.is_complete(150, 0, 0));
+    assert_eq!(tetris_engine::scoring::level_for_lines(149), 15);
+    assert!(!GameModes::Marathon{endless: true}.is_complete(150, 0, 0));
+    assert_eq!(Settings::default().mode, marathon);
+}
+
+#[test]
+fn gravity_follows_the_guideline_curve_until_20g(){
+    use tetris_engine::scoring::tick_speed;
+    assert_eq!(tick_speed(1), 1000);
+    assert_eq!(tick_speed(2), 793);
+    assert_eq!(tick_speed(10), 64);
+    assert_eq!(tick_speed(13), 18);
+    // faster than a row per frame at 60fps is 20G from then on
+    for level in 14..1000{
+        assert_eq!(tick_speed(level), 0, "level {}", level);
+    }
+    assert_eq!(tick_speed(0), 1000);
+    for level in 1..14{
+        assert!(tick_speed(level)>tick_speed(level+1));
+    }
+}
+
+#[test]
+fn zen_pieces_stay_put_until_dropped(){
+    let mut game = Game::new(Settings{mode: GameModes::Zen, ..Settings::default()});
+    let start = game.board().falling_cells();
+    assert!(game.step(60_000, &[]).is_empty());
+    assert_eq!(game.board().falling_cells(), start);
+    // holding soft drop still works, and grounded pieces wait for a hard drop
+    game.step(0, &[Input::Press(Action::SoftDrop)]);
+    assert!(game.step(60_000, &[Input::Release(Action::SoftDrop)]).is_empty());
+    assert!(game.board().is_grounded());
+    assert!(game.step(60_000, &[]).is_empty());
+    assert!(game.board().is_grounded());
+    assert_eq!(game.step(0, &[Input::Press(Action::HardDrop)]).last(), Some(&GameEvent::PieceLocked{lines_cleared: 0}));
+}
+
+#[test]
+fn zen_never_tops_out(){
+    let mut game = Game::new(Settings{mode: GameModes::Zen, ..Settings::default()});
+    for _ in 0..500{
+        let events = game.step(10, &[Input::Press(Action::HardDrop)]);
+        assert!(!events.contains(&GameEvent::GameOver));
+    }
+    assert!(!game.is_over());
+    assert!(!game.board().is_empty());
+}
+
+#[test]
+fn zen_can_be_left_running_for_months(){
+    // left stays held the whole time, and with no repeat interval its timer never fires again after reaching the wall
+    let mut game = Game::new(Settings{mode: GameModes::Zen, hold_move_interval: 0, ..Settings::default()});
+    game.step(0, &[Input::Press(Action::Left)]);
+    for _ in 0..3{
+        game.step(u32::MAX, &[]);
+    }
+    assert_eq!(game.elapsed_ms(), 3*u32::MAX as u64);
+    assert_eq!(game.board().falling_cells().iter().map(|c| c.0).min(), Some(0));
+    assert_eq!(game.step(0, &[Input::Press(Action::HardDrop)]).last(), Some(&GameEvent::PieceLocked{lines_cleared: 0}));
+}
+
+#[test]
+fn removing_the_top_row_leaves_the_rest_of_the_stack(){
+    let mut board = tetris_engine::TetrisBoard::make(10,20,tetris_engine::TetrisPieceType::I);
+    assert!(!board.remove_top_row());
+    board.set_tile(0, 0, Some(tetris_engine::TetrisPieceType::O));
+    board.set_tile(3, 5, Some(tetris_engine::TetrisPieceType::O));
+    board.set_tile(4, 5, Some(tetris_engine::TetrisPieceType::O));
+    assert!(board.remove_top_row());
+    assert_eq!(board.tiles().iter().filter(|t| t.is_some()).count(), 1);
+    assert!(board.tiles()[0].is_some());
+    assert!(board.remove_top_row());
+    assert!(board.is_empty());
+}
@@ -0,0 +1,111 @@
+use tetris_engine::{Action, Game, Input, Randomizers, Rng, Settings, TetrisPieceType};
+
+fn sequence(kind: Randomizers, seed: u64, len: usize) -> Vec<TetrisPieceType>{
+    let mut rng = Rng::new(seed);
+    let mut randomizer = kind.start();
+    (0..len).map(|_| randomizer.next(&mut rng)).collect()
+}
+
+fn counts(pieces: &[TetrisPieceType]) -> [usize;7]{
+    let mut counts = [0;7];
+    for p in pieces{
+        counts[p.to_int() as usize]+=1;
+    }
+    counts
+}
+
+/// Fraction of pieces that are the same as the one before.
+fn repeat_rate(pieces: &[TetrisPieceType]) -> f64{
+    pieces.windows(2).filter(|w| w[0]==w[1]).count() as f64/(pieces.len()-1) as f64
+}
+
+#[test]
+fn bags_deal_every_piece_before_refilling(){
+    for chunk in sequence(Randomizers::RandomGenerator, 1, 700).chunks(7){
+        assert_eq!(counts(chunk), [1;7]);
+    }
+    for chunk in sequence(Randomizers::Bag14, 2, 700).chunks(14){
+        assert_eq!(counts(chunk), [2;7]);
+    }
+    for chunk in sequence(Randomizers::Bag7Plus1, 3, 800).chunks(8){
+        let c = counts(chunk);
+        assert!(c.iter().all(|n| *n>=1) && c.iter().sum::<usize>()==8, "{:?}", c);
+    }
+}
+
+#[test]
+fn the_7_bag_shuffle_is_unbiased(){
+    // how often each piece comes first and last in a bag, over a lot of bags
+    let pieces = sequence(Randomizers::RandomGenerator, 4, 7*7000);
+    let firsts = pieces.chunks(7).map(|b| b[0]).collect::<Vec<TetrisPieceType>>();
+    let lasts = pieces.chunks(7).map(|b| b[6]).collect::<Vec<TetrisPieceType>>();
+    for c in [counts(&firsts), counts(&lasts)]{
+        assert!(c.iter().all(|n| (850..1150).contains(n)), "{:?}", c);
+    }
+}
+
+#[test]
+fn the_game_queue_keeps_to_bag_boundaries(){
+    let mut game = Game::new(Settings{seed: Some(8), queue_display_len: 4, board_height: 60, ..Settings::default()});
+    let mut played = Vec::new();
+    for _ in 0..21{
+        played.push(game.board().falling_piece());
+        game.step(0, &[Input::Press(Action::HardDrop)]);
+    }
+    for chunk in played.chunks(7){
+        assert_eq!(counts(chunk), [1;7]);
+    }
+}
+
+#[test]
+fn tgm_randomizers_never_start_with_s_z_or_o(){
+    for seed in 0..200{
+        for kind in [Randomizers::Tgm1, Randomizers::Tgm3]{
+            let first = sequence(kind, seed, 1)[0];
+            assert!(![TetrisPieceType::S, TetrisPieceType::Z, TetrisPieceType::O].contains(&first));
+        }
+    }
+}
+
+#[test]
+fn history_randomizers_repeat_less_than_pure_random(){
+    let random = repeat_rate(&sequence(Randomizers::Random, 5, 20000));
+    let tgm1 = repeat_rate(&sequence(Randomizers::Tgm1, 5, 20000));
+    let tgm3 = repeat_rate(&sequence(Randomizers::Tgm3, 5, 20000));
+    assert!((0.12..0.17).contains(&random), "{}", random);
+    // all four rolls have to land in the history, the last on the piece before: (4/7)^3 * 1/7
+    assert!(tgm1<0.035, "{}", tgm1);
+    assert!(tgm3<tgm1, "{}", tgm3);
+    for kind in [Randomizers::Tgm1, Randomizers::Tgm3]{
+        assert!(counts(&sequence(kind, 6, 7000)).iter().all(|n| (850..1150).contains(n)));
+    }
+}
+
+#[test]
+fn tgm3_keeps_droughts_short(){
+    let pieces = sequence(Randomizers::Tgm3, 7, 20000);
+    let mut since = [0;7];
+    let mut longest = 0;
+    for p in pieces{
+        for s in since.iter_mut(){
+            *s+=1;
+        }
+        since[p.to_int() as usize]=0;
+        longest = longest.max(*since.iter().max().unwrap());
+    }
+    assert!(longest<25, "{}", longest);
+}
+
+#[test]
+fn nes_rerolls_repeats_once(){
+    // a repeat needs the first roll to be the last piece or the blank, then the reroll to land on it again: 2/8 * 1/7
+    let rate = repeat_rate(&sequence(Randomizers::Nes, 9, 20000));
+    assert!((0.025..0.047).contains(&rate), "{}", rate);
+}
+
+#[test]
+fn randomizers_are_reproducible(){
+    for kind in [Randomizers::RandomGenerator, Randomizers::Random, Randomizers::Bag14, Randomizers::Bag7Plus1, Randomizers::Tgm1, Randomizers::Tgm3, Randomizers::Nes]{
+        assert_eq!(sequence(kind.clone(), 10, 100), sequence(kind.clone(), 10, 100), "{}", kind);
+    }
+}
@@ -0,0 +1,227 @@
+use tetris_engine::replay::{TimedInput, ENGINE_VERSION, MAGIC, MAX_LENGTH_MS};
+use tetris_engine::{Action, CustomRotation, Game, GameModes, Input, Playback, Replay, Rng, RotationSystems, Settings};
+
+const ACTIONS: [Action;8] = [Action::Left, Action::Right, Action::SoftDrop, Action::HardDrop, Action::RotateClockwise, Action::RotateCounterClockwise, Action::Rotate180, Action::Hold];
+
+/// Plays `steps` frames of uneven length with presses and releases picked by `seed`, like a player mashing keys.
+fn mash(settings: Settings, seed: u64, steps: usize) -> Game{
+    let mut game = Game::new(settings);
+    let mut rng = Rng::new(seed);
+    for _ in 0..steps{
+        let elapsed = rng.below(40);
+        let inputs: Vec<Input> = (0..rng.below(3)).map(|_| {
+            let action = ACTIONS[rng.below(8) as usize];
+            if rng.chance(60) {Input::Press(action)} else {Input::Release(action)}
+        }).collect();
+        game.step(elapsed, &inputs);
+        if game.is_over() {break}
+    }
+    game
+}
+
+/// Everything visible about a game, to compare two of them.
+fn snapshot(game: &Game) -> String{
+    format!("{:?} {:?} {:?} {} {} {} {} {} {} {:?}", game.board().tiles(), game.held_piece(), game.piece_queue(), game.score(), game.lines_cleared(),
+        game.pieces_placed(), game.level(), game.elapsed_ms(), game.is_over(), game.board().has_falling_piece().then(|| game.board().falling_cells()))
+}
+
+#[test]
+fn playback_reproduces_the_game(){
+    // seeds are fixed so the mashing reliably gets more than a few pieces down before topping out
+    for (i, settings) in [Settings{seed: Some(11), ..Settings::default()}, Settings{seed: Some(7), entry_delay: 100, line_clear_delay: 200, irs: true, ihs: true, ..Settings::default()},
+        Settings{seed: Some(8), mode: GameModes::Master, ..Settings::default()}, Settings{seed: Some(1), mode: GameModes::Survival{interval_ms: 2000}, ..Settings::default()}].into_iter().enumerate(){
+        let game = mash(settings, i as u64, 3000);
+        assert!(game.pieces_placed()>10);
+        let replay = game.replay();
+        assert_eq!(replay.length, game.elapsed_ms());
+        let mut playback = Playback::new(replay);
+        playback.seek(u64::MAX);
+        assert!(playback.is_done());
+        assert_eq!(snapshot(playback.game()), snapshot(&game));
+    }
+}
+
+#[test]
+fn replay_keeps_the_seed(){
+    let game = Game::new(Settings::default());
+    assert_eq!(game.replay().settings.seed, Some(game.seed()));
+    assert!(game.replay().inputs.is_empty());
+}
+
+#[test]
+fn inputs_are_timestamped_in_game_time(){
+    let mut game = Game::new(Settings::default());
+    game.step(100, &[Input::Press(Action::Left)]);
+    game.step(50, &[]);
+    game.step(25, &[Input::Release(Action::Left), Input::Press(Action::HardDrop)]);
+    assert_eq!(game.replay().inputs, vec![TimedInput{time: 100, input: Input::Press(Action::Left)}, TimedInput{time: 175, input: Input::Release(Action::Left)},
+        TimedInput{time: 175, input: Input::Press(Action::HardDrop)}]);
+    assert_eq!(game.replay().length, 175);
+}
+
+#[test]
+fn seeking_back_and_forth(){
+    let game = mash(Settings::default(), 3, 2000);
+    let replay = game.replay();
+    let middle = replay.length/2;
+    let mut straight = Playback::new(replay.clone());
+    straight.seek(middle);
+    let mut seeking = Playback::new(replay.clone());
+    seeking.seek(replay.length);
+    seeking.seek(middle/3);
+    seeking.seek(middle);
+    assert_eq!(seeking.time(), middle);
+    assert_eq!(snapshot(seeking.game()), snapshot(straight.game()));
+    // small steps land in the same place as one big one
+    let mut stepped = Playback::new(replay);
+    while stepped.time()<middle{
+        stepped.advance(7.min(middle-stepped.time()));
+    }
+    assert_eq!(snapshot(stepped.game()), snapshot(straight.game()));
+}
+
+#[test]
+fn json_round_trip(){
+    let bars = CustomRotation::parse("base ars\n* 0: 0,0 1,0 2,0 3,0").unwrap();
+    let settings = Settings{rotation_system: RotationSystems::Custom(bars), mode: GameModes::Dig{lines: 18, messiness: 40}, ..Settings::default()};
+    let replay = mash(settings, 5, 500).replay();
+    assert_eq!(Replay::from_json(&replay.to_json()), Ok(replay.clone()));
+    assert_eq!(Replay::decode(replay.to_json().as_bytes()), Ok(replay));
+    assert!(Replay::from_json("{\"format_version\":1,\"settings\": 3}").is_err());
+    assert!(Replay::decode(b"nonsense").is_err());
+}
+
+#[test]
+fn binary_round_trip(){
+    let replay = mash(Settings{mode: GameModes::Sprint{lines: 40}, ..Settings::default()}, 6, 3000).replay();
+    let bytes = replay.to_bytes();
+    assert!(bytes.starts_with(MAGIC));
+    assert_eq!(Replay::from_bytes(&bytes), Ok(replay.clone()));
+    assert_eq!(Replay::decode(&bytes), Ok(replay.clone()));
+    assert!(bytes.len()<replay.to_json().len()/5);
+    assert!(bytes.len()<300+replay.inputs.len()*4);
+    assert!(Replay::from_bytes(&bytes[..bytes.len()/2]).is_err());
+}
+
+#[test]
+fn header_and_deltas(){
+    let mut game = Game::new(Settings{seed: Some(42), ..Settings::default()});
+    game.step(412, &[Input::Press(Action::Left)]);
+    game.step(96, &[Input::Release(Action::Left)]);
+    let json = game.replay().to_json();
+    assert!(json.starts_with(&format!("{{\"format_version\":1,\"header\":{{\"engine_version\":\"{}\",\"ruleset\":1,\"seed\":42,\"settings\":{{", ENGINE_VERSION)));
+    assert!(json.ends_with("\"result\":{\"score\":0,\"lines\":0,\"time_ms\":508},\"events\":[[412,{\"Press\":\"Left\"}],[96,{\"Release\":\"Left\"}]]}"));
+    // the seed is only in the header
+    assert_eq!(json.matches("seed").count(), 1);
+}
+
+#[test]
+fn other_versions_are_refused(){
+    let json = Game::new(Settings::default()).replay().to_json();
+    let newer = json.replacen("\"format_version\":1", "\"format_version\":2", 1);
+    assert!(Replay::from_json(&newer).unwrap_err().contains("format 2"));
+    let other_rules = json.replacen("\"ruleset\":1", "\"ruleset\":7", 1);
+    assert!(Replay::from_json(&other_rules).unwrap_err().contains("ruleset 7"));
+    let mut bytes = Game::new(Settings::default()).replay().to_bytes();
+    bytes[MAGIC.len()] = 2;
+    assert!(Replay::from_bytes(&bytes).unwrap_err().contains("format 2"));
+}
+
+#[test]
+fn unsupported_settings_are_refused(){
+    let json = Game::new(Settings::default()).replay().to_json();
+    let huge = json.replacen("\"board_width\":10", "\"board_width\":4000000000", 1);
+    assert!(Replay::from_json(&huge).unwrap_err().contains("board width 4000000000"));
+    let no_room = json.replacen("\"buffer_height\":3", "\"buffer_height\":0", 1);
+    assert!(Replay::from_json(&no_room).unwrap_err().contains("buffer height"));
+    let huge = Replay{settings: Settings{board_height: 1<<40, ..Settings::default()}, ..Game::new(Settings::default()).replay()};
+    assert!(Replay::from_bytes(&huge.to_bytes()).unwrap_err().contains("board height"));
+    let custom = Settings{rotation_system: RotationSystems::Custom(CustomRotation::parse("base srs").unwrap()), ..Settings::default()};
+    let broken = Game::new(custom).replay().to_json().replacen("\"cells\":[[", "\"cells\":[[[0,0],[0,0],[0,0],[0,0]],[", 1);
+    assert!(Replay::from_json(&broken).unwrap_err().contains("custom rotation"));
+}
+
+#[test]
+fn overlong_replays_are_refused(){
+    let zen = Game::new(Settings{mode: GameModes::Zen, ..Settings::default()}).replay();
+    let json = zen.to_json().replacen("\"time_ms\":0", "\"time_ms\":18446744073709551615", 1);
+    assert!(Replay::from_json(&json).unwrap_err().contains("at most"));
+    assert!(Replay::from_bytes(&Replay{length: MAX_LENGTH_MS+1, ..zen.clone()}.to_bytes()).is_err());
+    assert!(Replay::from_bytes(&Replay{length: MAX_LENGTH_MS, ..zen}.to_bytes()).is_ok());
+    // a game that has ended isn't stepped on through the rest of the claimed time
+    let mut game = Game::new(Settings::default());
+    while !game.is_over(){
+        game.step(300, &[Input::Press(Action::HardDrop)]);
+    }
+    let endless = Replay{length: u64::MAX, ..game.replay()};
+    let mut playback = Playback::new(endless.clone());
+    playback.seek(u64::MAX);
+    assert!(playback.is_done());
+    assert_eq!(snapshot(playback.game()), snapshot(&game));
+    assert!(endless.verify().err().expect("the edited replay should fail").contains("time"));
+}
+
+#[test]
+fn verify_checks_the_claimed_result(){
+    let game = mash(Settings::default(), 8, 3000);
+    assert!(game.score()>0);
+    let replay = game.replay();
+    let verified = replay.verify().unwrap();
+    assert_eq!((verified.score(), verified.lines_cleared(), verified.elapsed_ms()), (game.score(), game.lines_cleared(), game.elapsed_ms()));
+
+    let better_score = Replay{score: replay.score+100, ..replay.clone()};
+    assert!(better_score.verify().err().expect("the edited replay should fail").contains("score"));
+    let more_lines = Replay{lines: replay.lines+1, ..replay.clone()};
+    assert!(more_lines.verify().err().expect("the edited replay should fail").contains("lines"));
+    let mut edited = replay.clone();
+    edited.inputs.retain(|i| i.input!=Input::Press(Action::HardDrop));
+    assert!(edited.verify().is_err());
+    let late = Replay{inputs: vec![TimedInput{time: replay.length+1, input: Input::Press(Action::HardDrop)}], ..replay.clone()};
+    assert!(late.verify().is_err());
+}
+
+#[test]
+fn verify_checks_the_time(){
+    let mut game = Game::new(Settings::default());
+    while !game.is_over(){
+        game.step(300, &[Input::Press(Action::HardDrop)]);
+    }
+    let replay = game.replay();
+    assert!(replay.verify().is_ok_and(|g| g.is_over()));
+    // the game can't have gone on for longer than it did before topping out
+    let longer = Replay{length: replay.length+1000, ..replay.clone()};
+    assert!(longer.verify().err().expect("the edited replay should fail").contains("time"));
+    let shorter = Replay{length: replay.length-1, ..replay};
+    assert!(shorter.verify().err().expect("the edited replay should fail").contains("after the end"));
+}
+
+#[test]
+fn replay_verify_command(){
+    let game = mash(Settings{seed: Some(9), ..Settings::default()}, 9, 100_000);
+    assert!(game.is_over());
+    let replay = game.replay();
+    // the same game stopped partway through, with its result at that point
+    let short = mash(Settings{seed: Some(9), ..Settings::default()}, 9, 100);
+    assert!(!short.is_over());
+    let good = std::env::temp_dir().join(format!("replay-verify-good-{}.ttr", std::process::id()));
+    let bad = std::env::temp_dir().join(format!("replay-verify-bad-{}.json", std::process::id()));
+    let unfinished = std::env::temp_dir().join(format!("replay-verify-unfinished-{}.ttr", std::process::id()));
+    std::fs::write(&good, replay.to_bytes()).unwrap();
+    std::fs::write(&bad, Replay{score: replay.score+1, ..replay.clone()}.to_json()).unwrap();
+    std::fs::write(&unfinished, short.replay().to_bytes()).unwrap();
+    let run = |paths: &[&std::path::Path]| std::process::Command::new(env!("CARGO_BIN_EXE_replay-verify")).args(paths).output().unwrap();
+
+    let passed = run(&[&good]);
+    assert!(passed.status.success());
+    assert!(String::from_utf8_lossy(&passed.stdout).contains(": ok, Marathon with seed 9, score"));
+    let failed = run(&[&good, &bad]);
+    assert!(!failed.status.success());
+    assert!(String::from_utf8_lossy(&failed.stdout).contains(": FAILED, score claimed as"));
+    let cut_short = run(&[&unfinished]);
+    assert!(!cut_short.status.success());
+    assert!(String::from_utf8_lossy(&cut_short.stdout).contains(": FAILED, the game was still going"));
+
+    let _ = std::fs::remove_file(good);
+    let _ = std::fs::remove_file(bad);
+    let _ = std::fs::remove_file(unfinished);
+}
@@ -0,0 +1,67 @@
+use tetris_engine::{Action, Game, GameModes, Input, Randomizers, Rng, Settings, TetrisPieceType};
+
+/// Pieces the game goes through when every piece is hard dropped as it comes.
+fn pieces_played(settings: Settings, count: usize) -> Vec<TetrisPieceType>{
+    let mut game = Game::new(settings);
+    let mut pieces = Vec::new();
+    while pieces.len()<count && !game.is_over(){
+        pieces.push(game.board().falling_piece());
+        game.step(0, &[Input::Press(Action::HardDrop)]);
+    }
+    pieces
+}
+
+#[test]
+fn matches_the_pcg32_reference_output(){
+    // first numbers from the PCG reference demo, seeded with 42 on stream 54
+    let mut rng = Rng::with_stream(42, 54);
+    let expected = [0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e];
+    assert_eq!(expected.map(|_| rng.next_u32()), expected);
+}
+
+#[test]
+fn below_stays_in_range_and_covers_it(){
+    let mut rng = Rng::new(7);
+    let mut counts = [0;7];
+    for _ in 0..7000{
+        counts[rng.below(7) as usize]+=1;
+    }
+    assert!(counts.iter().all(|c| (800..1200).contains(c)), "{:?}", counts);
+    assert_eq!(rng.below(1), 0);
+    assert!(!rng.chance(0));
+    assert!(rng.chance(100));
+}
+
+#[test]
+fn the_same_seed_plays_the_same_pieces(){
+    for randomizer in [Randomizers::RandomGenerator, Randomizers::Random]{
+        let settings = Settings{seed: Some(1234), randomizer, board_height: 60, ..Settings::default()};
+        let first = pieces_played(settings.clone(), 30);
+        assert_eq!(first.len(), 30);
+        assert_eq!(pieces_played(settings.clone(), 30), first);
+        assert_ne!(pieces_played(Settings{seed: Some(4321), ..settings}, 30), first);
+    }
+}
+
+#[test]
+fn games_keep_their_seed_across_restarts(){
+    let mut game = Game::new(Settings{seed: Some(99), ..Settings::default()});
+    let queue = game.piece_queue().clone();
+    game.restart();
+    assert_eq!(game.seed(), 99);
+    assert_eq!(game.piece_queue(), &queue);
+    // without one, each game picks its own
+    let unseeded = Game::new(Settings::default());
+    assert_ne!(Game::new(Settings::default()).seed(), unseeded.seed());
+}
+
+#[test]
+fn garbage_doesnt_change_the_pieces(){
+    let dig = Settings{seed: Some(5), mode: GameModes::Dig{lines: 10, messiness: 50}, ..Settings::default()};
+    let a = Game::new(dig.clone());
+    let b = Game::new(Settings{mode: GameModes::Dig{lines: 10, messiness: 0}, ..dig});
+    assert_eq!(a.piece_queue(), b.piece_queue());
+    let rows = |g: &Game| (0..10).map(|r| (0..10).find(|c| !g.board().is_garbage(*c, r))).collect::<Vec<Option<isize>>>();
+    let again = Game::new(Settings{seed: Some(5), mode: GameModes::Dig{lines: 10, messiness: 50}, ..Settings::default()});
+    assert_eq!(rows(&a), rows(&again));
+}
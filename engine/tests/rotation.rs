@@ -0,0 +1,151 @@
+use tetris_engine::{CustomRotation, Kicks180, RotationSystem, RotationSystems, TetrisBoard, TetrisPieceType};
+
+const PIECES: [TetrisPieceType;7] = [TetrisPieceType::I, TetrisPieceType::J, TetrisPieceType::L, TetrisPieceType::O, TetrisPieceType::S, TetrisPieceType::T, TetrisPieceType::Z];
+
+fn falling_cells(board: &TetrisBoard) -> Vec<(isize,isize)>{
+    let (w,h) = board.dimentions();
+    (0..h).flat_map(|r| (0..w).map(move |c| (c,r))).filter(|(c,r)| board.check_loc_for_falling_piece(*c, *r)).collect()
+}
+
+fn systems() -> Vec<RotationSystems>{
+    vec![RotationSystems::Srs, RotationSystems::Ars, RotationSystems::Classic, RotationSystems::Custom(CustomRotation::from_base(&RotationSystems::Ars))]
+}
+
+/// A board with the piece moved down into open space so no rotation needs to kick.
+fn open_board(piece: TetrisPieceType) -> TetrisBoard{
+    open_board_with(piece, RotationSystems::Srs)
+}
+fn open_board_with(piece: TetrisPieceType, system: RotationSystems) -> TetrisBoard{
+    let mut board = TetrisBoard::new(10,20,3,piece,system);
+    for _ in 0..8{
+        board.move_down();
+    }
+    board
+}
+
+#[test]
+fn counter_clockwise_undoes_clockwise(){
+    for system in systems(){
+        for piece in PIECES{
+            let mut board = open_board_with(piece, system.clone());
+            for _ in 0..4{
+                let start = falling_cells(&board);
+                assert!(board.rotate_clockwise());
+                assert!(board.rotate_counter_clockwise());
+                assert_eq!(falling_cells(&board), start, "{} {}", system, piece);
+                assert!(board.rotate_clockwise());
+            }
+            assert_eq!(board.falling_rot(), 0);
+        }
+    }
+}
+
+#[test]
+fn half_turn_matches_two_quarter_turns_in_open_space(){
+    for system in systems(){
+        for kicks in [Kicks180::SrsPlus, Kicks180::NoKicks]{
+            for piece in PIECES{
+                let mut turned = open_board_with(piece, system.clone());
+                let mut twice = open_board_with(piece, system.clone());
+                for _ in 0..4{
+                    assert!(turned.rotate_180(kicks));
+                    assert!(twice.rotate_clockwise());
+                    assert!(twice.rotate_clockwise());
+                    assert_eq!(turned.falling_rot(), twice.falling_rot());
+                    assert_eq!(falling_cells(&turned), falling_cells(&twice), "{} {} {}", system, piece, kicks);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn every_system_spawns_above_the_visible_field(){
+    for system in systems(){
+        for piece in PIECES{
+            let board = TetrisBoard::new(10,20,3,piece,system.clone());
+            assert_eq!(board.falling_cells().iter().map(|c| c.1).min(), Some(20), "{} {}", system, piece);
+        }
+    }
+}
+
+#[test]
+fn counter_clockwise_kicks_off_the_left_wall(){
+    let mut board = open_board(TetrisPieceType::T);
+    // stand the T up pointing left and push it against the left wall
+    assert!(board.rotate_counter_clockwise());
+    while board.move_left(){}
+    assert_eq!(falling_cells(&board).iter().map(|c| c.0).min(), Some(0));
+    // pointing down would stick out of the wall, so SRS kicks it one column right
+    assert!(board.rotate_counter_clockwise());
+    assert_eq!(board.falling_rot(), 2);
+    assert_eq!(falling_cells(&board).iter().map(|c| c.0).min(), Some(0));
+}
+
+#[test]
+fn ars_kicks_right_off_the_left_wall(){
+    let mut board = TetrisBoard::new(10,20,3,TetrisPieceType::T,RotationSystems::Ars);
+    // pointing right against the wall; pointing down needs the column left of it
+    assert!(board.place_falling_piece(TetrisPieceType::T, 3, -1, 5));
+    assert!(board.rotate_clockwise());
+    assert_eq!(board.falling_rot(), 0);
+    assert_eq!(board.falling_cells().iter().map(|c| c.0).min(), Some(0));
+}
+
+#[test]
+fn ars_center_column_rule_stops_kicks(){
+    // T pointing up, turning counter-clockwise to point left
+    let mut blocked_center = TetrisBoard::new(10,20,3,TetrisPieceType::T,RotationSystems::Ars);
+    assert!(blocked_center.place_falling_piece(TetrisPieceType::T, 2, 4, 5));
+    blocked_center.set_tile(5, 7, Some(TetrisPieceType::O));
+    assert!(!blocked_center.rotate_counter_clockwise());
+    assert_eq!(blocked_center.falling_rot(), 2);
+
+    let mut blocked_side = TetrisBoard::new(10,20,3,TetrisPieceType::T,RotationSystems::Ars);
+    assert!(blocked_side.place_falling_piece(TetrisPieceType::T, 2, 4, 5));
+    blocked_side.set_tile(4, 6, Some(TetrisPieceType::O));
+    assert!(blocked_side.rotate_counter_clockwise());
+    assert_eq!(blocked_side.falling_rot(), 1);
+    assert_eq!(blocked_side.falling_cells().iter().map(|c| c.0).min(), Some(5));
+}
+
+#[test]
+fn ars_i_never_kicks(){
+    let mut board = TetrisBoard::new(10,20,3,TetrisPieceType::I,RotationSystems::Ars);
+    assert!(board.place_falling_piece(TetrisPieceType::I, 1, -2, 5));
+    assert!(!board.rotate_clockwise());
+    assert!(!board.rotate_counter_clockwise());
+}
+
+#[test]
+fn classic_never_kicks(){
+    let mut board = TetrisBoard::new(10,20,3,TetrisPieceType::T,RotationSystems::Classic);
+    assert!(board.place_falling_piece(TetrisPieceType::T, 3, -1, 5));
+    assert!(!board.rotate_clockwise());
+    assert!(!board.rotate_180(Kicks180::SrsPlus));
+    assert_eq!(board.falling_rot(), 3);
+}
+
+#[test]
+fn custom_definitions_override_their_base(){
+    let custom = CustomRotation::parse("base classic\n# stubby I\nI 0: 0,1 1,1 2,1 3,1\n* 0>1: 0,0 1,0\n").unwrap();
+    let ars = CustomRotation::parse("base ars").unwrap();
+    for piece in PIECES{
+        for rot in 0..4{
+            assert_eq!(ars.cells(piece, rot), RotationSystems::Ars.cells(piece, rot));
+        }
+        assert_eq!(custom.kicks(piece, 0, 1, &|_,_| false), vec![(0,0),(1,0)]);
+        assert_eq!(custom.kicks(piece, 1, 2, &|_,_| false), vec![(0,0)]);
+    }
+    assert_eq!(custom.cells(TetrisPieceType::I, 0), [(0,1),(1,1),(2,1),(3,1)]);
+    assert_eq!(custom.cells(TetrisPieceType::T, 2), RotationSystems::Classic.cells(TetrisPieceType::T, 2));
+}
+
+#[test]
+fn custom_definitions_report_the_bad_line(){
+    for (definition, line) in [("base tgm", 1), ("T 0: 0,1 1,1 2,1", 1), ("\nT 4: 0,1 1,1 2,1 1,2", 2), ("T 0: 0,1 1,1 2,1 4,1", 1), ("Q 0>1: 0,0", 1), ("T 1>1: 0,0", 1), ("T 0>1 0,0", 1),
+        ("# stubby I\nI 0: 0,1 1,1 2,1 3,1\nbase ars", 3), ("base srs\nbase ars", 2)]{
+        let err = CustomRotation::parse(definition).unwrap_err();
+        assert!(err.starts_with(&format!("line {}:", line)), "{:?} gave {:?}", definition, err);
+    }
+}
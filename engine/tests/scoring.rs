@@ -0,0 +1,37 @@
+use tetris_engine::scoring::{back_to_back_bonus, combo_score, line_clear_score, perfect_clear_score, t_spin_score};
+use tetris_engine::{TSpin, TetrisBoard, TetrisPieceType};
+
+#[test]
+fn back_to_back_is_half_again(){
+    assert_eq!(back_to_back_bonus(line_clear_score(4, 1)), 1200);
+    assert_eq!(back_to_back_bonus(t_spin_score(TSpin::Full, 2, 2)), 3600);
+    assert_eq!(back_to_back_bonus(t_spin_score(TSpin::Mini, 1, 1)), 300);
+}
+
+#[test]
+fn combos_pay_fifty_per_step_per_level(){
+    assert_eq!(combo_score(0, 5), 0);
+    assert_eq!(combo_score(1, 1), 50);
+    assert_eq!(combo_score(4, 3), 600);
+}
+
+#[test]
+fn perfect_clears_pay_per_guideline(){
+    assert_eq!((1..5).map(|n| perfect_clear_score(n, false, 1)).collect::<Vec<u32>>(), vec![800,1200,1800,2000]);
+    assert_eq!(perfect_clear_score(4, true, 1), 3200);
+    assert_eq!(perfect_clear_score(2, true, 2), 2400);
+}
+
+#[test]
+fn clearing_every_tile_empties_the_board(){
+    let mut board = TetrisBoard::make(10,20,TetrisPieceType::I);
+    assert!(board.is_empty());
+    for c in 0..6{
+        board.set_tile(c, 0, Some(TetrisPieceType::O));
+    }
+    assert!(!board.is_empty());
+    assert!(board.place_falling_piece(TetrisPieceType::I, 0, 6, 5));
+    board.drop();
+    assert_eq!(board.clear_lines(), 1);
+    assert!(board.is_empty());
+}
@@ -0,0 +1,343 @@
+//! SRS conformance: known kick scenarios run against `TetrisBoard`.
+//!
+//! Boards are drawn top row first and only the bottom rows are given; everything above is empty.
+//! `#` is a locked tile and the piece letter marks the falling piece.
+
+use tetris_engine::rotation::Srs;
+use tetris_engine::{Kicks180, RotationSystem, TetrisBoard, TetrisPieceType};
+
+const PIECES: [TetrisPieceType;7] = [TetrisPieceType::I, TetrisPieceType::J, TetrisPieceType::L, TetrisPieceType::O, TetrisPieceType::S, TetrisPieceType::T, TetrisPieceType::Z];
+
+#[derive(Clone, Copy)]
+enum Turn{
+    Clockwise,
+    CounterClockwise,
+    Half(Kicks180)
+}
+
+struct KickCase{
+    name: &'static str,
+    piece: TetrisPieceType,
+    from: usize,
+    turn: Turn,
+    before: &'static [&'static str],
+    /// Expected board after the rotation, or None if the rotation must fail and leave the piece alone.
+    after: Option<(usize, &'static [&'static str])>
+}
+
+const CASES: &[KickCase] = &[
+    KickCase{name: "T floor kick, 0->R uses (-1,+1)", piece: TetrisPieceType::T, from: 0, turn: Turn::Clockwise,
+        before: &[
+            "....T.....",
+            "...TTT....",
+        ],
+        after: Some((1, &[
+            "...T......",
+            "...TT.....",
+            "...T......",
+        ]))},
+    KickCase{name: "T floor kick, 0->L uses (+1,+1)", piece: TetrisPieceType::T, from: 0, turn: Turn::CounterClockwise,
+        before: &[
+            "....T.....",
+            "...TTT....",
+        ],
+        after: Some((3, &[
+            ".....T....",
+            "....TT....",
+            ".....T....",
+        ]))},
+    KickCase{name: "TST kick, 0->R uses (-1,-2)", piece: TetrisPieceType::T, from: 0, turn: Turn::Clockwise,
+        before: &[
+            "#T........",
+            "TTT.......",
+            ".#########",
+            "..########",
+            ".#########",
+        ],
+        after: Some((1, &[
+            "#.........",
+            "..........",
+            "T#########",
+            "TT########",
+            "T#########",
+        ]))},
+    KickCase{name: "TST kick, 0->L uses (+1,-2)", piece: TetrisPieceType::T, from: 0, turn: Turn::CounterClockwise,
+        before: &[
+            "........T#",
+            ".......TTT",
+            "#########.",
+            "########..",
+            "#########.",
+        ],
+        after: Some((3, &[
+            ".........#",
+            "..........",
+            "#########T",
+            "########TT",
+            "#########T",
+        ]))},
+    KickCase{name: "I wall kick off the left wall, R->2 uses (+2,0)", piece: TetrisPieceType::I, from: 1, turn: Turn::Clockwise,
+        before: &[
+            "I.........",
+            "I.........",
+            "I.........",
+            "I.........",
+        ],
+        after: Some((2, &[
+            "..........",
+            "..........",
+            "IIII......",
+            "..........",
+        ]))},
+    KickCase{name: "I wall kick off the right wall, L->2 uses (-2,0)", piece: TetrisPieceType::I, from: 3, turn: Turn::CounterClockwise,
+        before: &[
+            ".........I",
+            ".........I",
+            ".........I",
+            ".........I",
+        ],
+        after: Some((2, &[
+            "..........",
+            "..........",
+            "......IIII",
+            "..........",
+        ]))},
+    KickCase{name: "I wall kick off the right wall, R->0 uses (-1,0)", piece: TetrisPieceType::I, from: 1, turn: Turn::CounterClockwise,
+        before: &[
+            ".........I",
+            ".........I",
+            ".........I",
+            ".........I",
+        ],
+        after: Some((0, &[
+            "..........",
+            "......IIII",
+            "..........",
+            "..........",
+        ]))},
+    KickCase{name: "I floor kick, 0->R uses (+1,+2)", piece: TetrisPieceType::I, from: 0, turn: Turn::Clockwise,
+        before: &[
+            "...IIII...",
+        ],
+        after: Some((1, &[
+            "......I...",
+            "......I...",
+            "......I...",
+            "......I...",
+        ]))},
+    KickCase{name: "I floor kick, 0->L uses (-1,+2)", piece: TetrisPieceType::I, from: 0, turn: Turn::CounterClockwise,
+        before: &[
+            "...IIII...",
+        ],
+        after: Some((3, &[
+            "...I......",
+            "...I......",
+            "...I......",
+            "...I......",
+        ]))},
+    KickCase{name: "J wall kick off the right wall, L->0 uses (-1,0)", piece: TetrisPieceType::J, from: 3, turn: Turn::Clockwise,
+        before: &[
+            ".........J",
+            ".........J",
+            "........JJ",
+        ],
+        after: Some((0, &[
+            ".......J..",
+            ".......JJJ",
+            "..........",
+        ]))},
+    KickCase{name: "S rotates in place when nothing is in the way", piece: TetrisPieceType::S, from: 0, turn: Turn::Clockwise,
+        before: &[
+            "....SS....",
+            "...SS.....",
+            "..........",
+        ],
+        after: Some((1, &[
+            "....S.....",
+            "....SS....",
+            ".....S....",
+        ]))},
+    KickCase{name: "fully enclosed T cannot rotate", piece: TetrisPieceType::T, from: 0, turn: Turn::Clockwise,
+        before: &[
+            "##########",
+            "##########",
+            "####T#####",
+            "###TTT####",
+        ],
+        after: None},
+    KickCase{name: "O never kicks, even when enclosed", piece: TetrisPieceType::O, from: 0, turn: Turn::Clockwise,
+        before: &[
+            "####OO####",
+            "####OO####",
+        ],
+        after: Some((1, &[
+            "####OO####",
+            "####OO####",
+        ]))},
+    KickCase{name: "O never kicks on a half turn", piece: TetrisPieceType::O, from: 0, turn: Turn::Half(Kicks180::SrsPlus),
+        before: &[
+            "####OO####",
+            "####OO####",
+        ],
+        after: Some((2, &[
+            "####OO####",
+            "####OO####",
+        ]))},
+    KickCase{name: "SRS+ 180 floor kick, 0->2 uses (0,+1)", piece: TetrisPieceType::T, from: 0, turn: Turn::Half(Kicks180::SrsPlus),
+        before: &[
+            "....T.....",
+            "...TTT....",
+        ],
+        after: Some((2, &[
+            "...TTT....",
+            "....T.....",
+        ]))},
+    KickCase{name: "180 without kicks fails on the floor", piece: TetrisPieceType::T, from: 0, turn: Turn::Half(Kicks180::NoKicks),
+        before: &[
+            "....T.....",
+            "...TTT....",
+        ],
+        after: None},
+];
+
+fn piece_char(piece: TetrisPieceType) -> char{
+    piece.to_string().chars().next().unwrap()
+}
+
+/// Cells marked with the piece letter, as (column, row) with row 0 at the bottom.
+fn marked_cells(rows: &[&str], piece: TetrisPieceType) -> Vec<(isize,isize)>{
+    let mut cells = Vec::new();
+    for (i,row) in rows.iter().enumerate(){
+        for (c,ch) in row.chars().enumerate(){
+            if ch==piece_char(piece){
+                cells.push((c as isize, (rows.len()-1-i) as isize));
+            }
+        }
+    }
+    cells.sort();
+    cells
+}
+
+fn sorted_falling_cells(board: &TetrisBoard) -> Vec<(isize,isize)>{
+    let mut cells = board.falling_cells().to_vec();
+    cells.sort();
+    cells
+}
+
+/// Builds the board drawn in `rows` with the falling piece in rotation `rot`.
+fn board_from(rows: &[&str], piece: TetrisPieceType, rot: usize) -> TetrisBoard{
+    let mut board = TetrisBoard::make(10,20,piece);
+    for (i,row) in rows.iter().enumerate(){
+        for (c,ch) in row.chars().enumerate(){
+            if ch=='#'{
+                board.set_tile(c as isize, (rows.len()-1-i) as isize, Some(TetrisPieceType::I));
+            }
+        }
+    }
+    let cells = marked_cells(rows, piece);
+    let shape = Srs.cells(piece, rot);
+    let col = cells.iter().map(|c| c.0).min().unwrap()-shape.iter().map(|c| c.0).min().unwrap();
+    let row = cells.iter().map(|c| c.1).min().unwrap()-shape.iter().map(|c| c.1).min().unwrap();
+    assert!(board.place_falling_piece(piece, rot, col, row), "piece does not fit where it is drawn");
+    assert_eq!(sorted_falling_cells(&board), cells, "drawn piece does not match rotation {}", rot);
+    board
+}
+
+#[test]
+fn kick_scenarios(){
+    for case in CASES{
+        let mut board = board_from(case.before, case.piece, case.from);
+        let rotated = match case.turn{
+            Turn::Clockwise => board.rotate_clockwise(),
+            Turn::CounterClockwise => board.rotate_counter_clockwise(),
+            Turn::Half(kicks) => board.rotate_180(kicks)
+        };
+        match case.after{
+            Some((to, rows)) => {
+                assert!(rotated, "{}: rotation failed", case.name);
+                assert_eq!(board.falling_rot(), to, "{}", case.name);
+                assert_eq!(sorted_falling_cells(&board), marked_cells(rows, case.piece), "{}", case.name);
+            }
+            None => {
+                assert!(!rotated, "{}: rotation should have failed", case.name);
+                assert_eq!(board.falling_rot(), case.from, "{}", case.name);
+                assert_eq!(sorted_falling_cells(&board), marked_cells(case.before, case.piece), "{}", case.name);
+            }
+        }
+    }
+}
+
+#[test]
+fn tst_kick_clears_three_lines(){
+    let case = &CASES[2];
+    let mut board = board_from(case.before, case.piece, case.from);
+    assert!(board.rotate_clockwise());
+    assert_eq!(board.drop(), 0);
+    assert_eq!(board.clear_lines(), 3);
+}
+
+#[test]
+fn quarter_turn_kicks_are_symmetric(){
+    // SRS kicks for a->b are always the negation of b->a
+    for piece in PIECES{
+        for from in 0..4{
+            for to in [from+1, from+3]{
+                let forward = Srs.kicks(piece, from, to%4, &|_,_| false);
+                let back = Srs.kicks(piece, to%4, from, &|_,_| false);
+                assert_eq!(forward.len(), back.len());
+                for (f,b) in forward.iter().zip(back.iter()){
+                    assert_eq!((f.0,f.1), (-b.0,-b.1), "{} {}->{}", piece, from, to%4);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn only_o_has_no_kicks(){
+    for piece in PIECES{
+        for from in 0..4{
+            let expected = if piece==TetrisPieceType::O {1} else {5};
+            assert_eq!(Srs.kicks(piece, from, (from+1)%4, &|_,_| false).len(), expected);
+            assert_eq!(Srs.kicks(piece, from, (from+3)%4, &|_,_| false).len(), expected);
+            assert_eq!(Srs.kicks(piece, from, from, &|_,_| false)[0], (0,0));
+        }
+    }
+}
+
+#[test]
+fn pieces_never_wrap_around_the_walls(){
+    for piece in PIECES{
+        for rot in 0..4{
+            let mut board = TetrisBoard::make(10,20,piece);
+            for _ in 0..8{ board.move_down(); }
+            for _ in 0..rot{ assert!(board.rotate_clockwise()); }
+            let rows = |b: &TetrisBoard| b.falling_cells().map(|c| c.1);
+            let start_rows = rows(&board);
+            while board.move_left(){}
+            assert_eq!(board.falling_cells().iter().map(|c| c.0).min(), Some(0), "{} rot {}", piece, rot);
+            assert!(!board.move_left());
+            assert_eq!(rows(&board), start_rows, "{} rot {} changed rows moving left", piece, rot);
+            while board.move_right(){}
+            assert_eq!(board.falling_cells().iter().map(|c| c.0).max(), Some(9), "{} rot {}", piece, rot);
+            assert!(!board.move_right());
+            assert_eq!(rows(&board), start_rows, "{} rot {} changed rows moving right", piece, rot);
+        }
+    }
+}
+
+#[test]
+fn pieces_spawn_per_guideline(){
+    // lowest cells in the first row above the 20 visible rows; I and O in the middle columns, the rest left of middle
+    let expected_columns = |piece: TetrisPieceType| match piece{
+        TetrisPieceType::I => (3,6),
+        TetrisPieceType::O => (4,5),
+        _ => (3,5)
+    };
+    for piece in PIECES{
+        let board = TetrisBoard::make(10,20,piece);
+        let cells = board.falling_cells();
+        assert_eq!(cells.iter().map(|c| c.1).min(), Some(20), "{}", piece);
+        assert_eq!((cells.iter().map(|c| c.0).min().unwrap(), cells.iter().map(|c| c.0).max().unwrap()), expected_columns(piece), "{}", piece);
+    }
+}
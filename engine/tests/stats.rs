@@ -0,0 +1,75 @@
+use tetris_engine::stats::RandomizerStats;
+use tetris_engine::{Randomizers, TetrisPieceType};
+
+const PIECES: [TetrisPieceType;7] = [TetrisPieceType::I, TetrisPieceType::J, TetrisPieceType::L, TetrisPieceType::O, TetrisPieceType::S, TetrisPieceType::T, TetrisPieceType::Z];
+
+#[test]
+fn bag_stats(){
+    let stats = RandomizerStats::sample(&Randomizers::RandomGenerator, 5, 7000);
+    assert_eq!(stats.counts, [1000;7]);
+    for piece in PIECES{
+        assert_eq!(stats.frequency(piece), 1.0/7.0);
+        assert!(stats.max_drought(piece)<=12);
+        assert!((stats.mean_drought(piece)-6.0).abs()<0.05);
+    }
+    assert!(stats.longest_snake_run()>=1 && stats.longest_snake_run()<=4);
+    // one drought per piece dealt, plus the ones still going at the end for all but the last piece
+    assert_eq!(stats.drought_histogram.iter().sum::<u64>(), 7006);
+}
+
+#[test]
+fn droughts_still_going_at_the_end_count(){
+    // five pieces from a bag leave two out entirely
+    let stats = RandomizerStats::sample(&Randomizers::RandomGenerator, 2, 5);
+    let missing: Vec<TetrisPieceType> = PIECES.into_iter().filter(|p| stats.counts[p.to_int() as usize]==0).collect();
+    assert_eq!(missing.len(), 2);
+    for piece in missing{
+        assert_eq!(stats.max_drought(piece), 5);
+        assert_eq!(stats.mean_drought(piece), 5.0);
+    }
+    assert_eq!(stats.drought_histogram[5], 2);
+}
+
+#[test]
+fn snake_runs_cover_every_s_and_z(){
+    let stats = RandomizerStats::sample(&Randomizers::Random, 9, 50_000);
+    let snakes = stats.counts[TetrisPieceType::S.to_int() as usize]+stats.counts[TetrisPieceType::Z.to_int() as usize];
+    let in_runs: u64 = stats.snake_runs.iter().enumerate().map(|(len, &runs)| len as u64*runs).sum();
+    assert_eq!(in_runs, snakes);
+    // A memoryless randomizer goes far longer without an I piece than a bag ever can.
+    assert!(stats.max_drought(TetrisPieceType::I)>12);
+}
+
+#[test]
+fn same_seed_same_stats(){
+    let a = RandomizerStats::sample(&Randomizers::Tgm3, 3, 10_000);
+    let b = RandomizerStats::sample(&Randomizers::Tgm3, 3, 10_000);
+    assert_eq!(a.to_csv(), b.to_csv());
+    assert_eq!(a.to_json(), b.to_json());
+}
+
+#[test]
+fn exports(){
+    let stats = RandomizerStats::sample(&Randomizers::Nes, 1, 1000);
+    let csv = stats.to_csv();
+    assert!(csv.starts_with("piece,count,frequency,mean_drought,max_drought\n"));
+    assert!(csv.contains("\ndrought,count\n"));
+    assert!(csv.contains("\nsnake_run,count\n"));
+    let json = stats.to_json();
+    assert!(json.starts_with("{\"randomizer\":\"NES\",\"seed\":1,\"pieces\":1000,"));
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["per_piece"]["T"]["count"], stats.counts[TetrisPieceType::T.to_int() as usize]);
+    assert_eq!(value["per_piece"]["I"]["max_drought"], stats.max_drought(TetrisPieceType::I));
+    assert_eq!(value["longest_snake_run"], stats.longest_snake_run());
+    assert_eq!(value["drought_histogram"].as_array().map(|h| h.len()), Some(stats.drought_histogram.len()));
+    assert!(stats.to_text().contains("1000 pieces from NES with seed 1"));
+}
+
+#[test]
+fn randomizer_names(){
+    for kind in [Randomizers::RandomGenerator, Randomizers::Random, Randomizers::Bag14, Randomizers::Bag7Plus1, Randomizers::Tgm1, Randomizers::Tgm3, Randomizers::Nes]{
+        assert_eq!(kind.to_string().parse::<Randomizers>(), Ok(kind.clone()));
+    }
+    assert_eq!("tgm3".parse::<Randomizers>(), Ok(Randomizers::Tgm3));
+    assert!("shuffle".parse::<Randomizers>().is_err());
+}
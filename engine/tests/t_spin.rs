@@ -0,0 +1,111 @@
+use tetris_engine::scoring::{line_clear_score, t_spin_score};
+use tetris_engine::{Kicks180, TSpin, TetrisBoard, TetrisPieceType};
+
+/// Builds a board from rows drawn top first (`#` locked, `T` the falling T in rotation `rot`).
+fn board_from(rows: &[&str], rot: usize) -> TetrisBoard{
+    let mut board = TetrisBoard::make(10,20,TetrisPieceType::T);
+    let mut cells = Vec::new();
+    for (i,row) in rows.iter().enumerate(){
+        let r = (rows.len()-1-i) as isize;
+        for (c,ch) in row.chars().enumerate(){
+            match ch{
+                '#' => board.set_tile(c as isize, r, Some(TetrisPieceType::I)),
+                'T' => cells.push((c as isize, r)),
+                _ => {}
+            }
+        }
+    }
+    // SRS keeps the T's center at (1,1) of its box in every rotation
+    let center = cells.iter().find(|c| cells.iter().filter(|o| (c.0-o.0).abs()+(c.1-o.1).abs()==1).count()==3).unwrap();
+    assert!(board.place_falling_piece(TetrisPieceType::T, rot, center.0-1, center.1-1));
+    board
+}
+
+#[test]
+fn t_spin_double_with_an_overhang(){
+    let mut board = board_from(&[
+        "..#T......",
+        "##.TT#####",
+        "###T######",
+    ], 1);
+    assert!(board.rotate_clockwise());
+    assert_eq!(board.t_spin(), Some(TSpin::Full));
+    board.drop();
+    assert_eq!(board.clear_lines(), 2);
+}
+
+#[test]
+fn one_front_corner_is_a_mini(){
+    let mut board = board_from(&[
+        "..#T#.....",
+        "##.TT#####",
+        "###T.#####",
+    ], 1);
+    assert!(board.rotate_clockwise());
+    assert_eq!(board.t_spin(), Some(TSpin::Mini));
+    board.drop();
+    assert_eq!(board.clear_lines(), 1);
+}
+
+#[test]
+fn tst_kick_upgrades_a_mini(){
+    // only one corner in front of the T is filled, but it needed the fifth kick test to get in
+    let mut board = board_from(&[
+        "#T........",
+        "TTT.......",
+        ".#########",
+        "..########",
+        "..########",
+    ], 0);
+    assert!(board.rotate_clockwise());
+    assert_eq!(board.falling_cells().iter().map(|c| c.1).min(), Some(0));
+    assert_eq!(board.t_spin(), Some(TSpin::Full));
+}
+
+#[test]
+fn fifth_half_turn_kick_stays_a_mini(){
+    // the half turn only fits with the fifth SRS+ kick, which isn't a TST kick
+    let mut board = board_from(&[
+        "....#.....",
+        "..........",
+        "..#T#.....",
+        "..#TT.....",
+        "...T......",
+        "..........",
+        "..........",
+    ], 1);
+    assert!(board.rotate_180(Kicks180::SrsPlus));
+    assert!(board.falling_cells().contains(&(3,6)));
+    assert_eq!(board.t_spin(), Some(TSpin::Mini));
+}
+
+#[test]
+fn two_corners_are_not_a_t_spin(){
+    let mut board = board_from(&[
+        "...T......",
+        "...TT.....",
+        "###T.#####",
+    ], 1);
+    assert!(board.rotate_clockwise());
+    assert_eq!(board.t_spin(), None);
+}
+
+#[test]
+fn only_t_pieces_spin(){
+    let mut board = TetrisBoard::make(10,20,TetrisPieceType::S);
+    for c in 0..10{
+        for r in 0..3{
+            board.set_tile(c, r, Some(TetrisPieceType::I));
+        }
+    }
+    assert!(board.place_falling_piece(TetrisPieceType::S, 0, 3, 2));
+    assert_eq!(board.t_spin(), None);
+}
+
+#[test]
+fn t_spins_score_per_guideline(){
+    assert_eq!((0..4).map(|n| t_spin_score(TSpin::Full, n, 1)).collect::<Vec<u32>>(), vec![400,800,1200,1600]);
+    assert_eq!((0..3).map(|n| t_spin_score(TSpin::Mini, n, 1)).collect::<Vec<u32>>(), vec![100,200,400]);
+    assert_eq!(t_spin_score(TSpin::Full, 2, 3), 3600);
+    assert!(t_spin_score(TSpin::Full, 1, 1)>line_clear_score(1, 1));
+}
@@ -1,11 +1,15 @@
-use std::{collections::VecDeque, io::BufRead, fmt::{Display, Formatter, Result}, clone};
-
 use yew::prelude::*;
-use web_sys::{HtmlInputElement, TouchEvent, TouchList, Touch, Element, Event, window, HtmlDocument};
-use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
-use gloo::{console::{self, Timer, dirxml}, timers::callback, events::EventListener, utils::document};
-use gloo::timers::callback::{Interval, Timeout};
-use rand::Rng;
+use web_sys::{HtmlElement, HtmlInputElement, HtmlTextAreaElement, TouchEvent, Event, HtmlDocument, window};
+use wasm_bindgen::JsCast;
+use gloo::utils::document;
+use gloo::events::EventListener;
+use gloo::file::callbacks::{read_as_bytes, FileReader};
+use gloo::file::{Blob, ObjectUrl};
+use gloo::timers::callback::Interval;
+use tetris_engine::mode::MARATHON_LINES;
+use tetris_engine::settings::{MAX_BOARD_HEIGHT, MAX_BOARD_WIDTH, MAX_BUFFER_HEIGHT, MIN_BOARD_HEIGHT, MIN_BOARD_WIDTH, MIN_BUFFER_HEIGHT};
+use tetris_engine::master::{self, Grade};
+use tetris_engine::{Action, CustomRotation, Game, GameEvent, GameModes, Input, Kicks180, Playback, Randomizers, Replay, RotationSystem, RotationSystems, Settings, TetrisBoard, TetrisPieceType};
 
 fn main() {
     println!("Hello, world!");
@@ -30,9 +34,36 @@ enum Windows{
 
 struct RootComponent{
     game_settings: Settings,
+    touch_horiz_sens: i32,
+    custom_rotation: String,
+    custom_rotation_error: Option<String>,
     displaying_window: Windows,
     colors: Vec<String>,
     highscores: Vec<u32>,
+    /// Personal best times for each of `SPRINT_LINE_TARGETS`.
+    sprint_bests: Vec<Vec<u64>>,
+    /// Line target used when switching to Sprint.
+    sprint_lines: u32,
+    /// Personal best times for each of `DIG_LINE_TARGETS`.
+    dig_bests: Vec<Vec<u64>>,
+    /// Garbage rows to clear when switching to Dig.
+    dig_lines: u32,
+    /// Percent chance each garbage row's hole moves in Dig.
+    dig_messiness: u32,
+    /// Best scores for Ultra at `ultra_seconds`.
+    ultra_bests: Vec<u64>,
+    /// Length of an Ultra game in seconds.
+    ultra_seconds: u32,
+    /// Longest times survived in Survival starting at `survival_seconds`.
+    survival_bests: Vec<u64>,
+    /// Seconds before garbage first rises in Survival.
+    survival_seconds: u32,
+    /// Best grades reached in Master, as ranks.
+    master_grades: Vec<u64>,
+    /// Fastest time for each Master section.
+    master_sections: Vec<u64>,
+    /// Whether Marathon keeps going past level 15.
+    marathon_endless: bool,
     cookie_notif: bool,
     cookies: String
 }
@@ -41,48 +72,28 @@ impl Component for RootComponent{
 
     type Properties = ();
 
-    fn create(ctx: &Context<Self>) -> Self {
+    fn create(_ctx: &Context<Self>) -> Self {
         let get_cookies = document().unchecked_into::<HtmlDocument>().cookie().unwrap_or(String::from("None"));
-        let mut colors: Vec<String> = vec![String::from("#2c2a29"),String::from("#333333"),String::from("#222222"),String::from("#a7a7a7"),String::from("#ffffff"),String::from("#00ffff"),String::from("#ffff00")
-        ,String::from("#ff00ff"),String::from("#ffa500"),String::from("#0000ff"),String::from("#ff0000"),String::from("#00ff00"), String::from("70"), String::from("#000000"), String::from("#000000"), String::from("70")];
-        let mut game_settings = Settings::default();
-        if get_cookies!="None"{
-            // colors = Vec::new();
-            for v in get_cookies.split("; "){
-                match v.split_once('='){
-                    Some((name, value)) => {
-                        if name.starts_with("saved_color_"){
-                            colors[name.split_at(12).1.parse::<usize>().unwrap_or(0)] = String::from(value);
-                        }else{
-                            match name{
-                                "hold_time" => game_settings.hold_time=value.parse::<u32>().unwrap_or(game_settings.hold_time),
-                                "hold_move_interval" => game_settings.hold_move_interval=value.parse::<u32>().unwrap_or(game_settings.hold_move_interval),
-                                "max_switches" => game_settings.max_num_held_piece_switches=value.parse::<u32>().unwrap_or(1),
-                                "randomizer" => game_settings.randomizer=match value {"Random" => Randomizers::Random, _ => Randomizers::RandomGenerator},
-                                "lock_delay" => game_settings.lock_delay=value.parse::<u32>().unwrap_or(500),
-                                "moves_before_lock" => game_settings.moves_before_lock=value.parse::<u32>().unwrap_or(15),
-                                "touch_horiz_sens" => game_settings.touch_horiz_sens=value.parse::<i32>().unwrap_or(25),
-                                "down_hold_time" => game_settings.down_hold_time=value.parse::<u32>().unwrap_or(game_settings.down_hold_time),
-                                "down_hold_move_interval" => game_settings.down_hold_move_interval=value.parse::<u32>().unwrap_or(game_settings.down_hold_move_interval),
-                                _ => {}
-                            }
-                        }
-                    }
-                    None => {
-                        // this cookie does not exist. Error? idk
-                    }
-                }
-            }
-        }
-        Self{game_settings: Settings::default(), displaying_window: Windows::Game, colors, highscores: Self::get_highscores().unwrap_or_default(), cookies: get_cookies, cookie_notif: false}
+        let mut root = Self{game_settings: Settings::default(), touch_horiz_sens: 25, custom_rotation: String::new(), custom_rotation_error: None, displaying_window: Windows::Game, colors: Self::default_colors(),
+            highscores: Self::get_highscores().unwrap_or_default(), sprint_bests: Self::get_bests("sprint_best", &SPRINT_LINE_TARGETS), sprint_lines: 40, dig_bests: Self::get_bests("dig_best", &DIG_LINE_TARGETS), dig_lines: 10, dig_messiness: 30, ultra_bests: Vec::new(), ultra_seconds: 120, survival_bests: Vec::new(), survival_seconds: 10, master_grades: Self::get_records(MASTER_GRADE_COOKIE), master_sections: Self::get_records(MASTER_SECTION_COOKIE), marathon_endless: false, cookies: get_cookies.clone(), cookie_notif: false};
+        root.load_cookies(&get_cookies);
+        root.ultra_bests=Self::get_records(&ultra_best_cookie(root.ultra_seconds));
+        root.survival_bests=Self::get_records(&survival_best_cookie(root.survival_seconds));
+        root
     }
 
-    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg{
             SettingsMsg::ChangeWindow(w) => {
                 self.cookie_notif=false;
                 if w==Windows::Highscores{
                     self.highscores=Self::get_highscores().unwrap_or_default();
+                    self.sprint_bests=Self::get_bests("sprint_best", &SPRINT_LINE_TARGETS);
+                    self.dig_bests=Self::get_bests("dig_best", &DIG_LINE_TARGETS);
+                    self.ultra_bests=Self::get_records(&ultra_best_cookie(self.ultra_seconds));
+                    self.survival_bests=Self::get_records(&survival_best_cookie(self.survival_seconds));
+                    self.master_grades=Self::get_records(MASTER_GRADE_COOKIE);
+                    self.master_sections=Self::get_records(MASTER_SECTION_COOKIE);
                 }
                 self.displaying_window = w;
             }
@@ -104,7 +115,15 @@ impl Component for RootComponent{
                         self.game_settings.queue_display_len=value.parse::<usize>().unwrap_or(self.game_settings.queue_display_len);
                     }
                     4 => {
-                        self.game_settings.randomizer=match self.game_settings.randomizer{Randomizers::Random => Randomizers::RandomGenerator, _ => Randomizers::Random}
+                        self.game_settings.randomizer=match self.game_settings.randomizer{
+                            Randomizers::RandomGenerator => Randomizers::Random,
+                            Randomizers::Random => Randomizers::Bag14,
+                            Randomizers::Bag14 => Randomizers::Bag7Plus1,
+                            Randomizers::Bag7Plus1 => Randomizers::Tgm1,
+                            Randomizers::Tgm1 => Randomizers::Tgm3,
+                            Randomizers::Tgm3 => Randomizers::Nes,
+                            Randomizers::Nes => Randomizers::RandomGenerator
+                        }
                     }
                     5 => {
                         self.game_settings.lock_delay=value.parse::<u32>().unwrap_or(500)
@@ -113,7 +132,7 @@ impl Component for RootComponent{
                         self.game_settings.moves_before_lock=value.parse::<u32>().unwrap_or(15)
                     }
                     7 => {
-                        self.game_settings.touch_horiz_sens=value.parse::<i32>().unwrap_or(25)
+                        self.touch_horiz_sens=value.parse::<i32>().unwrap_or(25)
                     }
                     8 => {
                         self.game_settings.down_hold_time=value.parse::<u32>().unwrap_or(self.game_settings.down_hold_time);
@@ -121,6 +140,97 @@ impl Component for RootComponent{
                     9 => {
                         self.game_settings.down_hold_move_interval=value.parse::<u32>().unwrap_or(self.game_settings.down_hold_move_interval);
                     }
+                    10 => {
+                        self.game_settings.kicks_180=match self.game_settings.kicks_180{Kicks180::SrsPlus => Kicks180::NoKicks, _ => Kicks180::SrsPlus}
+                    }
+                    11 => {
+                        self.game_settings.rotation_system=match self.game_settings.rotation_system{
+                            RotationSystems::Srs => RotationSystems::Ars,
+                            RotationSystems::Ars => RotationSystems::Classic,
+                            RotationSystems::Classic => self.parse_custom_rotation().unwrap_or(RotationSystems::Srs),
+                            RotationSystems::Custom(_) => RotationSystems::Srs
+                        }
+                    }
+                    12 => {
+                        self.custom_rotation=value;
+                        let custom = self.parse_custom_rotation();
+                        if let (Some(custom), RotationSystems::Custom(_)) = (custom, &self.game_settings.rotation_system){
+                            self.game_settings.rotation_system=custom;
+                        }
+                    }
+                    13 => {
+                        self.game_settings.board_width=value.parse::<usize>().map(|v| v.clamp(MIN_BOARD_WIDTH, MAX_BOARD_WIDTH)).unwrap_or(self.game_settings.board_width);
+                    }
+                    14 => {
+                        self.game_settings.board_height=value.parse::<usize>().map(|v| v.clamp(MIN_BOARD_HEIGHT, MAX_BOARD_HEIGHT)).unwrap_or(self.game_settings.board_height);
+                    }
+                    15 => {
+                        self.game_settings.buffer_height=value.parse::<usize>().map(|v| v.clamp(MIN_BUFFER_HEIGHT, MAX_BUFFER_HEIGHT)).unwrap_or(self.game_settings.buffer_height);
+                    }
+                    16 => {
+                        self.game_settings.mode=match self.game_settings.mode{
+                            GameModes::Marathon{..} => GameModes::Sprint{lines: self.sprint_lines},
+                            GameModes::Sprint{..} => GameModes::Ultra{duration_ms: self.ultra_seconds*1000},
+                            GameModes::Ultra{..} => GameModes::Zen,
+                            GameModes::Zen => GameModes::Dig{lines: self.dig_lines, messiness: self.dig_messiness},
+                            GameModes::Dig{..} => GameModes::Survival{interval_ms: self.survival_seconds*1000},
+                            GameModes::Survival{..} => GameModes::Master,
+                            GameModes::Master => GameModes::Marathon{endless: self.marathon_endless}
+                        }
+                    }
+                    17 => {
+                        let next = SPRINT_LINE_TARGETS.iter().position(|l| *l==self.sprint_lines).map_or(0, |i| (i+1)%SPRINT_LINE_TARGETS.len());
+                        self.sprint_lines=SPRINT_LINE_TARGETS[next];
+                        if let GameModes::Sprint{lines} = &mut self.game_settings.mode{
+                            *lines=self.sprint_lines;
+                        }
+                    }
+                    18 => {
+                        self.ultra_seconds=value.parse::<u32>().map(|v| v.clamp(10,3600)).unwrap_or(self.ultra_seconds);
+                        if let GameModes::Ultra{duration_ms} = &mut self.game_settings.mode{
+                            *duration_ms=self.ultra_seconds*1000;
+                        }
+                    }
+                    19 => {
+                        self.marathon_endless = !self.marathon_endless;
+                        if let GameModes::Marathon{endless} = &mut self.game_settings.mode{
+                            *endless=self.marathon_endless;
+                        }
+                    }
+                    20 => {
+                        let next = DIG_LINE_TARGETS.iter().position(|l| *l==self.dig_lines).map_or(0, |i| (i+1)%DIG_LINE_TARGETS.len());
+                        self.dig_lines=DIG_LINE_TARGETS[next];
+                        if let GameModes::Dig{lines, ..} = &mut self.game_settings.mode{
+                            *lines=self.dig_lines;
+                        }
+                    }
+                    21 => {
+                        self.dig_messiness=value.parse::<u32>().map(|v| v.min(100)).unwrap_or(self.dig_messiness);
+                        if let GameModes::Dig{messiness, ..} = &mut self.game_settings.mode{
+                            *messiness=self.dig_messiness;
+                        }
+                    }
+                    22 => {
+                        self.survival_seconds=value.parse::<u32>().map(|v| v.clamp(1,60)).unwrap_or(self.survival_seconds);
+                        if let GameModes::Survival{interval_ms} = &mut self.game_settings.mode{
+                            *interval_ms=self.survival_seconds*1000;
+                        }
+                    }
+                    23 => {
+                        self.game_settings.entry_delay=value.parse::<u32>().map(|v| v.min(1000)).unwrap_or(self.game_settings.entry_delay);
+                    }
+                    24 => {
+                        self.game_settings.line_clear_delay=value.parse::<u32>().map(|v| v.min(1000)).unwrap_or(self.game_settings.line_clear_delay);
+                    }
+                    25 => {
+                        self.game_settings.irs = !self.game_settings.irs;
+                    }
+                    26 => {
+                        self.game_settings.ihs = !self.game_settings.ihs;
+                    }
+                    27 => {
+                        self.game_settings.seed=parse_seed(&value).unwrap_or(self.game_settings.seed);
+                    }
                     _ => {
 
                     }
@@ -134,45 +244,25 @@ impl Component for RootComponent{
                         self.game_settings = Settings::default();
                     }
                     _ => {
-                        self.colors = vec![String::from("#2c2a29"),String::from("#333333"),String::from("#222222"),String::from("#a7a7a7"),String::from("#ffffff"),String::from("#00ffff"),String::from("#ffff00")
-                        ,String::from("#ff00ff"),String::from("#ffa500"),String::from("#0000ff"),String::from("#ff0000"),String::from("#00ff00"), String::from("70"), String::from("#000000"), String::from("#000000"), String::from("70")];
+                        self.colors = Self::default_colors();
                         self.game_settings = Settings::default();
                     }
                 }
             }
             SettingsMsg::Revert => {
                 let get_cookies = document().unchecked_into::<HtmlDocument>().cookie().unwrap_or(String::from("None"));
-                self.colors = vec![String::from("#2c2a29"),String::from("#333333"),String::from("#222222"),String::from("#a7a7a7"),String::from("#ffffff"),String::from("#00ffff"),String::from("#ffff00")
-                ,String::from("#ff00ff"),String::from("#ffa500"),String::from("#0000ff"),String::from("#ff0000"),String::from("#00ff00"), String::from("70"), String::from("#000000"), String::from("#000000"), String::from("70")];
+                self.colors = Self::default_colors();
                 self.game_settings = Settings::default();
-                if get_cookies!="None"{
-                    // colors = Vec::new();
-                    for v in get_cookies.split("; "){
-                        match v.split_once('='){
-                            Some((name, value)) => {
-                                if name.starts_with("saved_color_"){
-                                    self.colors[name.split_at(12).1.parse::<usize>().unwrap_or(0)] = String::from(value);
-                                }else{
-                                    match name{
-                                        "hold_time" => self.game_settings.hold_time=value.parse::<u32>().unwrap_or(self.game_settings.hold_time),
-                                        "hold_move_interval" => self.game_settings.hold_move_interval=value.parse::<u32>().unwrap_or(self.game_settings.hold_move_interval),
-                                        "max_switches" => self.game_settings.max_num_held_piece_switches=value.parse::<u32>().unwrap_or(1),
-                                        "randomizer" => self.game_settings.randomizer=match value {"Random" => Randomizers::Random, _ => Randomizers::RandomGenerator},
-                                        "lock_delay" => self.game_settings.lock_delay=value.parse::<u32>().unwrap_or(500),
-                                        "moves_before_lock" => self.game_settings.moves_before_lock=value.parse::<u32>().unwrap_or(15),
-                                        "touch_horiz_sens" => self.game_settings.touch_horiz_sens=value.parse::<i32>().unwrap_or(50),
-                                        "down_hold_time" => self.game_settings.down_hold_time=value.parse::<u32>().unwrap_or(self.game_settings.down_hold_time),
-                                        "down_hold_move_interval" => self.game_settings.down_hold_move_interval=value.parse::<u32>().unwrap_or(self.game_settings.down_hold_move_interval),
-                                        _ => {}
-                                    }
-                                }
-                            }
-                            None => {
-                                // this cookie does not exist. Error? idk
-                            }
-                        }
-                    }
-                }
+                self.touch_horiz_sens = 25;
+                self.sprint_lines = 40;
+                self.dig_lines = 10;
+                self.dig_messiness = 30;
+                self.ultra_seconds = 120;
+                self.survival_seconds = 10;
+                self.marathon_endless = false;
+                self.custom_rotation = String::new();
+                self.custom_rotation_error = None;
+                self.load_cookies(&get_cookies);
             }
             SettingsMsg::SaveCookies(force) => {
                 let doc = document().unchecked_into::<HtmlDocument>();
@@ -191,10 +281,29 @@ impl Component for RootComponent{
                 let _ = doc.set_cookie(&format!("max_switches={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.max_num_held_piece_switches));
                 let _ = doc.set_cookie(&format!("lock_delay={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.lock_delay));
                 let _ = doc.set_cookie(&format!("moves_before_lock={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.moves_before_lock));
-                let _ = doc.set_cookie(&format!("randomizer={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.randomizer.to_string()));
-                let _ = doc.set_cookie(&format!("touch_horiz_sens={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.touch_horiz_sens));
+                let _ = doc.set_cookie(&format!("entry_delay={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.entry_delay));
+                let _ = doc.set_cookie(&format!("line_clear_delay={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.line_clear_delay));
+                let _ = doc.set_cookie(&format!("irs={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.irs));
+                let _ = doc.set_cookie(&format!("ihs={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.ihs));
+                let _ = doc.set_cookie(&format!("seed={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.seed.map(|s| s.to_string()).unwrap_or_default()));
+                let _ = doc.set_cookie(&format!("randomizer={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.randomizer));
+                let _ = doc.set_cookie(&format!("kicks_180={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.kicks_180));
+                let _ = doc.set_cookie(&format!("rotation_system={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.rotation_system));
+                // cookies can't hold newlines
+                let _ = doc.set_cookie(&format!("custom_rotation={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.custom_rotation.replace('\n', "|")));
+                let _ = doc.set_cookie(&format!("touch_horiz_sens={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.touch_horiz_sens));
                 let _ = doc.set_cookie(&format!("down_hold_time={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.down_hold_time));
                 let _ = doc.set_cookie(&format!("down_hold_move_interval={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.down_hold_move_interval));
+                let _ = doc.set_cookie(&format!("board_width={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.board_width));
+                let _ = doc.set_cookie(&format!("board_height={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.board_height));
+                let _ = doc.set_cookie(&format!("buffer_height={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.game_settings.buffer_height));
+                let _ = doc.set_cookie(&format!("mode={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",mode_name(&self.game_settings.mode)));
+                let _ = doc.set_cookie(&format!("sprint_lines={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.sprint_lines));
+                let _ = doc.set_cookie(&format!("dig_lines={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.dig_lines));
+                let _ = doc.set_cookie(&format!("dig_messiness={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.dig_messiness));
+                let _ = doc.set_cookie(&format!("ultra_seconds={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.ultra_seconds));
+                let _ = doc.set_cookie(&format!("survival_seconds={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.survival_seconds));
+                let _ = doc.set_cookie(&format!("marathon_endless={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",self.marathon_endless));
             }
         }
         true
@@ -378,10 +487,106 @@ impl Component for RootComponent{
                     <input name="moves-before-lock" type="number" value={self.game_settings.moves_before_lock.to_string()} onchange={Self::get_settings_callback(link,6)}/>
                     </div>
                     <div class="horiz-section">
+                    <h1>{"entry delay"}</h1>
+                    <div class="text">{"Time in milliseconds after a piece locks before the next one appears, also called ARE (0 to 1000). Keys pressed meanwhile apply to the next piece. Master uses its own"}</div>
+                    <input name="entry-delay" type="number" min="0" max="1000" value={self.game_settings.entry_delay.to_string()} onchange={Self::get_settings_callback(link,23)}/>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"line clear delay"}</h1>
+                    <div class="text">{"Time in milliseconds cleared lines stay on the board before the rows above fall (0 to 1000). Master uses its own"}</div>
+                    <input name="line-clear-delay" type="number" min="0" max="1000" value={self.game_settings.line_clear_delay.to_string()} onchange={Self::get_settings_callback(link,24)}/>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"initial rotation (IRS)"}</h1>
+                    <div class="text">{"Whether holding a rotate key while the next piece appears spawns it already turned. It can save a piece that would otherwise top out"}</div>
+                    <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),25))}>{if self.game_settings.irs {"On"} else {"Off"}}</button>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"initial hold (IHS)"}</h1>
+                    <div class="text">{"Whether holding the hold key while the next piece appears holds it straight away"}</div>
+                    <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),26))}>{if self.game_settings.ihs {"On"} else {"Off"}}</button>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"seed"}</h1>
+                    <div class="text">{"Number the pieces and garbage come from. Games with the same seed get the same pieces, so you can race a friend on them or replay one from its end screen. Leave empty for a new seed every game"}</div>
+                    <input name="seed" type="text" inputmode="numeric" value={self.game_settings.seed.map(|s| s.to_string()).unwrap_or_default()} onchange={Self::get_settings_callback(link,27)}/>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"board width"}</h1>
+                    <div class="text">{"Number of columns on the board (4 to 40)"}</div>
+                    <input name="board-width" type="number" min="4" max="40" value={self.game_settings.board_width.to_string()} onchange={Self::get_settings_callback(link,13)}/>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"board height"}</h1>
+                    <div class="text">{"Number of visible rows on the board (4 to 60)"}</div>
+                    <input name="board-height" type="number" min="4" max="60" value={self.game_settings.board_height.to_string()} onchange={Self::get_settings_callback(link,14)}/>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"buffer height"}</h1>
+                    <div class="text">{"Number of hidden rows above the board where pieces spawn (2 to 40)"}</div>
+                    <input name="buffer-height" type="number" min="2" max="40" value={self.game_settings.buffer_height.to_string()} onchange={Self::get_settings_callback(link,15)}/>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"mode"}</h1>
+                    <div class="text">{"What to play (Marathon = score as much as you can through level 15, Sprint = clear the line target as fast as you can, Ultra = score as much as you can before time runs out, Zen = practice stacking with no gravity, lock delay or topping out, Dig = clear the garbage as fast as you can, Survival = last as long as you can as garbage rises faster and faster, Master = arcade style levels 0 to 999 up to 20G, graded 9 to GM)"}</div>
+                    <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),16))}>{mode_name(&self.game_settings.mode)}</button>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"sprint lines"}</h1>
+                    <div class="text">{"Number of lines to clear in Sprint"}</div>
+                    <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),17))}>{self.sprint_lines.to_string()}</button>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"dig lines"}</h1>
+                    <div class="text">{"Number of garbage lines to clear in Dig"}</div>
+                    <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),20))}>{self.dig_lines.to_string()}</button>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"dig messiness"}</h1>
+                    <div class="text">{"Chance in percent that each garbage row's hole is in a different column from the one above it (0 = one straight well, 100 = a new column every row)"}</div>
+                    <input name="dig-messiness" type="range" min=0 max=100 value={self.dig_messiness.to_string()} onchange={Self::get_settings_callback(link,21)}/>
+                    <h2>{format!("{}%", self.dig_messiness)}</h2>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"survival interval"}</h1>
+                    <div class="text">{"Seconds before garbage first rises in Survival (1 to 60). It rises a little faster every time after that."}</div>
+                    <input name="survival-interval" type="number" min="1" max="60" value={self.survival_seconds.to_string()} onchange={Self::get_settings_callback(link,22)}/>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"endless marathon"}</h1>
+                    <div class="text">{"Whether Marathon keeps going after level 15 until you top out"}</div>
+                    <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),19))}>{if self.marathon_endless {"On"} else {"Off"}}</button>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"ultra length"}</h1>
+                    <div class="text">{"Length of an Ultra game in seconds (10 to 3600)"}</div>
+                    <input name="ultra-length" type="number" min="10" max="3600" value={self.ultra_seconds.to_string()} onchange={Self::get_settings_callback(link,18)}/>
+                    </div>
+                    <div class="horiz-section">
                     <h1>{"randomizer"}</h1>
-                    <div class="text">{"Which randomizer algorithmn to use for generating next pieces (random = fully random, randomgenerator = randomly sorts 7 pieces at a time)"}</div>
+                    <div class="text">{"Which randomizer algorithmn to use for generating next pieces (random = fully random, randomgenerator = randomly sorts 7 pieces at a time, bag14 = the same with two of each piece, bag7plus1 = 7 pieces plus one random extra, TGM1 = avoids the last 4 pieces, TGM3 = avoids the last 4 pieces and brings back ones that haven't come in a while, NES = avoids repeating the last piece)"}</div>
                     <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),4))}>{self.game_settings.randomizer.to_string()}</button>
                     </div>
+                    <div class="horiz-section">
+                    <h1>{"rotation system"}</h1>
+                    <div class="text">{"How pieces turn and kick (SRS = modern guideline, ARS = TGM style, Classic = NES/Game Boy with no kicks, Custom = the definition below)"}</div>
+                    <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),11))}>{self.game_settings.rotation_system.to_string()}</button>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"custom rotation"}</h1>
+                    <div class="text">{"Definition for the Custom rotation system, one entry per line: \"base ars\" first to start from srs/ars/classic, \"T 0: 0,1 1,1 2,1 1,2\" for the cells of a rotation (col,row in a 4x4 box, row 0 at the bottom), \"T 0>1: 0,0 -1,0\" for the kicks tried between two rotations (* for every piece)"}</div>
+                    <div class="custom-rotation">
+                        <textarea rows="6" value={self.custom_rotation.clone()} onchange={link.callback(|e: Event| {let input: HtmlTextAreaElement = e.target_unchecked_into(); SettingsMsg::ChangeSettings(input.value(),12)})}/>
+                        if let Some(error) = &self.custom_rotation_error{
+                            <p>{error.clone()}</p>
+                        }
+                    </div>
+                    </div>
+                    <div class="horiz-section">
+                    <h1>{"180 kicks"}</h1>
+                    <div class="text">{"Kick table used when rotating 180 degrees with A (SRS+ = TETR.IO style kicks, NoKicks = the piece only turns if it fits in place)"}</div>
+                    <button onclick={link.callback(|_| SettingsMsg::ChangeSettings(String::new(),10))}>{self.game_settings.kicks_180.to_string()}</button>
+                    </div>
                     <div class="horiz-section">
                         <h1>{"theme"}</h1>
                         <div class="text">{"Toggles between themes"}</div>
@@ -390,7 +595,7 @@ impl Component for RootComponent{
                     <div class="horiz-section">
                         <h1>{"touch horizontal sensitivity"}</h1>
                         <div class="text">{"Touchscreen sensitivity for moving tetrominoes horizontally side to side"}</div>
-                        <input name="moves-before-lock" type="number" value={self.game_settings.touch_horiz_sens.to_string()} onchange={Self::get_settings_callback(link,7)}/>
+                        <input name="moves-before-lock" type="number" value={self.touch_horiz_sens.to_string()} onchange={Self::get_settings_callback(link,7)}/>
                     </div>
                     <div class="settings-footer">
                         <button onclick={link.callback(|_| SettingsMsg::Revert)}>{"revert"}</button>
@@ -408,8 +613,10 @@ impl Component for RootComponent{
                         </div>
                     }
                 }else if self.displaying_window==Windows::Highscores{
+                    <div class="highscore-section">
+                    <h1>{"Marathon"}</h1>
                     {
-                        self.highscores.iter().enumerate().map(|(i,h)| {
+                        self.highscores.iter().map(|h| {
                             html!{
                                 <div class="highscore-list-item">
                                     {h.to_string()}
@@ -417,19 +624,160 @@ impl Component for RootComponent{
                             }
                         }).collect::<Html>()
                     }
+                    </div>
+                    {
+                        SPRINT_LINE_TARGETS.iter().zip(self.sprint_bests.iter()).map(|(lines, times)| view_times(&format!("Sprint {}L", lines), times)).collect::<Html>()
+                    }
+                    {
+                        DIG_LINE_TARGETS.iter().zip(self.dig_bests.iter()).map(|(lines, times)| view_times(&format!("Dig {}L", lines), times)).collect::<Html>()
+                    }
+                    if !self.ultra_bests.is_empty(){
+                        <div class="highscore-section">
+                        <h1>{format!("Ultra {}", format_time(self.ultra_seconds as u64*1000))}</h1>
+                        {
+                            self.ultra_bests.iter().map(|s| {
+                                html!{
+                                    <div class="highscore-list-item">
+                                        {s.to_string()}
+                                    </div>
+                                }
+                            }).collect::<Html>()
+                        }
+                        </div>
+                    }
+                    {view_times(&format!("Survival {}s", self.survival_seconds), &self.survival_bests)}
+                    if !self.master_grades.is_empty(){
+                        <div class="highscore-section">
+                        <h1>{"Master grades"}</h1>
+                        {
+                            self.master_grades.iter().map(|g| {
+                                html!{
+                                    <div class="highscore-list-item">
+                                        {Grade::from_rank(*g as u32).to_string()}
+                                    </div>
+                                }
+                            }).collect::<Html>()
+                        }
+                        </div>
+                    }
+                    if !self.master_sections.is_empty(){
+                        <div class="highscore-section">
+                        <h1>{"Master sections"}</h1>
+                        {
+                            self.master_sections.iter().enumerate().map(|(i, t)| {
+                                html!{
+                                    <div class="highscore-list-item">
+                                        {format!("{}: {}", section_name(i), format_time(*t))}
+                                    </div>
+                                }
+                            }).collect::<Html>()
+                        }
+                        </div>
+                    }
                 }else{
-                <GameDisplay settings={self.game_settings.clone()}/>
+                <GameDisplay settings={self.game_settings.clone()} touch_horiz_sens={self.touch_horiz_sens}/>
                 }
             </div>
         }
     }
 }
 impl RootComponent{
+    fn default_colors() -> Vec<String>{
+        vec![String::from("#2c2a29"),String::from("#333333"),String::from("#222222"),String::from("#a7a7a7"),String::from("#ffffff"),String::from("#00ffff"),String::from("#ffff00")
+        ,String::from("#ff00ff"),String::from("#ffa500"),String::from("#0000ff"),String::from("#ff0000"),String::from("#00ff00"), String::from("70"), String::from("#000000"), String::from("#000000"), String::from("70")]
+    }
+    fn load_cookies(&mut self, cookies: &str){
+        if cookies=="None"{
+            return
+        }
+        let mut rotation_system = None;
+        let mut mode = None;
+        for v in cookies.split("; "){
+            match v.split_once('='){
+                Some((name, value)) => {
+                    if name.starts_with("saved_color_"){
+                        self.colors[name.split_at(12).1.parse::<usize>().unwrap_or(0)] = String::from(value);
+                    }else{
+                        match name{
+                            "hold_time" => self.game_settings.hold_time=value.parse::<u32>().unwrap_or(self.game_settings.hold_time),
+                            "hold_move_interval" => self.game_settings.hold_move_interval=value.parse::<u32>().unwrap_or(self.game_settings.hold_move_interval),
+                            "max_switches" => self.game_settings.max_num_held_piece_switches=value.parse::<u32>().unwrap_or(1),
+                            "randomizer" => self.game_settings.randomizer=match value {
+                                "Random" => Randomizers::Random,
+                                "Bag14" => Randomizers::Bag14,
+                                "Bag7Plus1" => Randomizers::Bag7Plus1,
+                                "TGM1" => Randomizers::Tgm1,
+                                "TGM3" => Randomizers::Tgm3,
+                                "NES" => Randomizers::Nes,
+                                _ => Randomizers::RandomGenerator
+                            },
+                            "kicks_180" => self.game_settings.kicks_180=match value {"NoKicks" => Kicks180::NoKicks, _ => Kicks180::SrsPlus},
+                            "rotation_system" => rotation_system=Some(String::from(value)),
+                            "custom_rotation" => self.custom_rotation=value.replace('|', "\n"),
+                            "lock_delay" => self.game_settings.lock_delay=value.parse::<u32>().unwrap_or(500),
+                            "moves_before_lock" => self.game_settings.moves_before_lock=value.parse::<u32>().unwrap_or(15),
+                            "entry_delay" => self.game_settings.entry_delay=value.parse::<u32>().map(|v| v.min(1000)).unwrap_or(self.game_settings.entry_delay),
+                            "line_clear_delay" => self.game_settings.line_clear_delay=value.parse::<u32>().map(|v| v.min(1000)).unwrap_or(self.game_settings.line_clear_delay),
+                            "irs" => self.game_settings.irs=value=="true",
+                            "ihs" => self.game_settings.ihs=value=="true",
+                            "seed" => self.game_settings.seed=parse_seed(value).unwrap_or(self.game_settings.seed),
+                            "touch_horiz_sens" => self.touch_horiz_sens=value.parse::<i32>().unwrap_or(25),
+                            "down_hold_time" => self.game_settings.down_hold_time=value.parse::<u32>().unwrap_or(self.game_settings.down_hold_time),
+                            "down_hold_move_interval" => self.game_settings.down_hold_move_interval=value.parse::<u32>().unwrap_or(self.game_settings.down_hold_move_interval),
+                            "board_width" => self.game_settings.board_width=value.parse::<usize>().map(|v| v.clamp(MIN_BOARD_WIDTH, MAX_BOARD_WIDTH)).unwrap_or(self.game_settings.board_width),
+                            "board_height" => self.game_settings.board_height=value.parse::<usize>().map(|v| v.clamp(MIN_BOARD_HEIGHT, MAX_BOARD_HEIGHT)).unwrap_or(self.game_settings.board_height),
+                            "buffer_height" => self.game_settings.buffer_height=value.parse::<usize>().map(|v| v.clamp(MIN_BUFFER_HEIGHT, MAX_BUFFER_HEIGHT)).unwrap_or(self.game_settings.buffer_height),
+                            "mode" => mode=Some(String::from(value)),
+                            "marathon_endless" => self.marathon_endless=value=="true",
+                            "survival_seconds" => self.survival_seconds=value.parse::<u32>().map(|v| v.clamp(1,60)).unwrap_or(self.survival_seconds),
+                            "ultra_seconds" => self.ultra_seconds=value.parse::<u32>().map(|v| v.clamp(10,3600)).unwrap_or(self.ultra_seconds),
+                            "dig_lines" => self.dig_lines=value.parse::<u32>().ok().filter(|l| DIG_LINE_TARGETS.contains(l)).unwrap_or(self.dig_lines),
+                            "dig_messiness" => self.dig_messiness=value.parse::<u32>().map(|v| v.min(100)).unwrap_or(self.dig_messiness),
+                            "sprint_lines" => self.sprint_lines=value.parse::<u32>().ok().filter(|l| SPRINT_LINE_TARGETS.contains(l)).unwrap_or(self.sprint_lines),
+                            _ => {}
+                        }
+                    }
+                }
+                None => {
+                    // this cookie does not exist. Error? idk
+                }
+            }
+        }
+        // the custom definition may come after the choice of system
+        self.game_settings.rotation_system=match rotation_system.as_deref(){
+            Some("ARS") => RotationSystems::Ars,
+            Some("Classic") => RotationSystems::Classic,
+            Some("Custom") => self.parse_custom_rotation().unwrap_or(RotationSystems::Srs),
+            _ => RotationSystems::Srs
+        };
+        self.game_settings.mode=match mode.as_deref(){
+            Some("Sprint") => GameModes::Sprint{lines: self.sprint_lines},
+            Some("Ultra") => GameModes::Ultra{duration_ms: self.ultra_seconds*1000},
+            Some("Zen") => GameModes::Zen,
+            Some("Survival") => GameModes::Survival{interval_ms: self.survival_seconds*1000},
+            Some("Dig") => GameModes::Dig{lines: self.dig_lines, messiness: self.dig_messiness},
+            Some("Master") => GameModes::Master,
+            _ => GameModes::Marathon{endless: self.marathon_endless}
+        };
+    }
+    /// Parses the custom rotation definition, remembering the error to show if it is malformed.
+    fn parse_custom_rotation(&mut self) -> Option<RotationSystems>{
+        match CustomRotation::parse(&self.custom_rotation){
+            Ok(custom) => {
+                self.custom_rotation_error=None;
+                Some(RotationSystems::Custom(custom))
+            }
+            Err(e) => {
+                self.custom_rotation_error=Some(e);
+                None
+            }
+        }
+    }
     fn get_color_callback(link: &yew::html::Scope<Self>, val: usize) -> yew::Callback<Event>{
-        return link.callback(move |e: Event| {let input: HtmlInputElement = e.target_unchecked_into(); SettingsMsg::ChangeColor(input.value().parse::<String>().unwrap(),val)})
+        link.callback(move |e: Event| {let input: HtmlInputElement = e.target_unchecked_into(); SettingsMsg::ChangeColor(input.value().parse::<String>().unwrap(),val)})
     }
     fn get_settings_callback(link: &yew::html::Scope<Self>, val: u32) -> yew::Callback<Event>{
-        return link.callback(move |e: Event| {let input: HtmlInputElement = e.target_unchecked_into(); SettingsMsg::ChangeSettings(input.value().parse::<String>().unwrap(),val)})
+        link.callback(move |e: Event| {let input: HtmlInputElement = e.target_unchecked_into(); SettingsMsg::ChangeSettings(input.value().parse::<String>().unwrap(),val)})
     }
     fn get_highscores() -> core::result::Result<Vec<u32>,String>{
         let doc = document().unchecked_into::<HtmlDocument>();
@@ -445,7 +793,45 @@ impl RootComponent{
             }
             return Err(String::from("Highscore cookie does not exist"))
         }
-        return Err(String::from("Cookies not enabled"))
+        Err(String::from("Cookies not enabled"))
+    }
+    /// Personal best times saved as `<prefix>_<lines>` for each line target, fastest first.
+    fn get_bests(prefix: &str, targets: &[u32]) -> Vec<Vec<u64>>{
+        targets.iter().map(|l| Self::get_records(&format!("{}_{}", prefix, l))).collect()
+    }
+    /// Records saved in the cookie `name`, best first.
+    fn get_records(name: &str) -> Vec<u64>{
+        let cookies = document().unchecked_into::<HtmlDocument>().cookie().unwrap_or_default();
+        cookies.split("; ").find_map(|c| c.strip_prefix(name).and_then(|v| v.strip_prefix('=')))
+            .map(|v| v.split(',').filter_map(|t| t.parse::<u64>().ok()).collect()).unwrap_or_default()
+    }
+    /// Adds `record` to the ones saved in the cookie `name`, keeping the best 8 (lowest first if `lower_is_better`).
+    /// Returns whether it is the new best.
+    fn add_record(name: &str, record: u64, lower_is_better: bool) -> bool{
+        let mut records = Self::get_records(name);
+        let pos = records.iter().position(|r| if lower_is_better {record<*r} else {record>*r}).unwrap_or(records.len());
+        records.insert(pos, record);
+        records.truncate(8);
+        let _ = document().unchecked_into::<HtmlDocument>().set_cookie(&format!("{}={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",name,records.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(",")));
+        pos==0
+    }
+    /// Keeps the fastest time for each Master section from the cumulative `times` a game finished them at.
+    /// Returns which sections were new bests.
+    fn add_section_times(times: &[u64]) -> Vec<bool>{
+        let mut bests = Self::get_records(MASTER_SECTION_COOKIE);
+        let new_bests = section_durations(times).into_iter().enumerate().map(|(i, d)| {
+            if i>=bests.len(){
+                bests.push(d);
+                true
+            }else if d<bests[i]{
+                bests[i]=d;
+                true
+            }else{
+                false
+            }
+        }).collect();
+        let _ = document().unchecked_into::<HtmlDocument>().set_cookie(&format!("{}={}; expires=Tue, 19 Jan 2038 03:14:07 UTC;",MASTER_SECTION_COOKIE,bests.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(",")));
+        new_bests
     }
     fn add_highscore(h: u32){
         let doc = document().unchecked_into::<HtmlDocument>();
@@ -478,54 +864,93 @@ impl RootComponent{
 
 #[derive(Properties,PartialEq, Clone)]
 struct GameProps{
-    settings: Settings
+    settings: Settings,
+    touch_horiz_sens: i32
 }
 
 enum GameMsg {
-    Left(InputTypes),
-    Right(InputTypes),
-    Down(InputTypes),
-    Drop,
+    Input(Input),
+    /// A press immediately followed by a release, used for touch gestures that move one step.
+    Tap(Action),
     Tick,
-    Hold,
-    Rotate,
-    CancelDown,
-    CancelRight,
-    CancelLeft,
+    Restart,
     TouchStart(TouchEvent),
     TouchMove(TouchEvent),
     TouchEnd(TouchEvent),
-    Unfocus,
+    /// Pauses a running game, as when focus is lost or the tab is hidden.
+    Pause,
+    Resume,
+    TogglePause,
+    /// Ends the paused game where it stands.
+    Quit,
+    /// Watches the replay of the current game.
+    WatchReplay,
+    /// Saves the replay being watched, or else of the current game, as a file.
+    DownloadReplay,
+    /// Reads a replay file picked by the player.
+    OpenReplay(Option<web_sys::File>),
+    /// A replay file finished reading, with its contents.
+    ReplayRead(Result<Vec<u8>, String>),
+    /// Plays or pauses the replay being watched.
+    ToggleReplay,
+    /// Moves the replay being watched to a game time.
+    SeekReplay(u64),
+    /// Goes to the next replay speed.
+    ChangeReplaySpeed,
+    /// Stops watching the replay, back to the game.
+    CloseReplay,
     None
 }
 
-#[derive(PartialEq)]
-enum InputTypes{
-    Tap,
-    Hold,
-    Touch
-}
+/// How often the game is stepped while running, in milliseconds.
+const FRAME_MS: u32 = 16;
+/// Line targets Sprint can be played with.
+const SPRINT_LINE_TARGETS: [u32;3] = [20, 40, 100];
+/// Garbage line counts Dig can be played with.
+const DIG_LINE_TARGETS: [u32;3] = [10, 18, 100];
+/// Cookie holding the best grades reached in Master.
+const MASTER_GRADE_COOKIE: &str = "master_best_grade";
+/// Cookie holding the fastest time for each Master section.
+const MASTER_SECTION_COOKIE: &str = "master_section_bests";
+/// How long clear announcements (T-spins and such) stay up, in milliseconds.
+const ANNOUNCEMENT_MS: f64 = 2000.0;
+/// Speeds replays can be watched at.
+const REPLAY_SPEEDS: [f64;6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+/// Index of normal speed in `REPLAY_SPEEDS`.
+const NORMAL_REPLAY_SPEED: usize = 2;
 
 struct GameDisplay{
-    game: TetrisBoard,
-    ticker_handle: Option<Timeout>,
-    move_handle: (bool,Option<Timeout>),
-    down_handle: (bool,Option<Timeout>),
-    stick_handle: Option<Timeout>,
-    level: u32,
-    score: u32,
-    lines_cleared: u32,
-    stick_counter: u32,
-    held_piece: Option<TetrisPieceType>,
-    held_piece_switch_count: u32,
-    piece_queue: VecDeque<TetrisPieceType>,
+    game: Game,
+    ticker_handle: Option<Interval>,
+    last_step: f64,
     touch_start_pos: (i32,i32),
     touch_translation: i32,
     touch_pos: (i32,i32),
-    touch_velocity: (i32,i32),
     touch_can_rotate: bool,
+    touch_horiz_sens: i32,
     game_end_screen: bool,
-    settings: Settings
+    /// Whether the finished game beat the personal best for its mode.
+    new_best: bool,
+    /// Which Master sections the finished game set the fastest time for.
+    section_bests: Vec<bool>,
+    /// Text announcing the last special clear and when it happened.
+    announcement: Option<(String, f64)>,
+    /// Whether the game is paused: nothing is stepped and the board is hidden until resumed.
+    paused: bool,
+    /// Pauses the game when the tab is hidden. Kept so it stays registered.
+    _visibility_listener: EventListener,
+    /// Replay being watched in place of the game.
+    playback: Option<Playback>,
+    /// Whether the replay being watched is playing rather than stopped.
+    replay_playing: bool,
+    /// Index into `REPLAY_SPEEDS` of the speed replays play at.
+    replay_speed: usize,
+    /// Why the last replay file couldn't be opened.
+    replay_error: Option<String>,
+    /// Read of an opened replay file. Kept so it isn't cancelled.
+    replay_reader: Option<FileReader>,
+    /// Link to the last downloaded replay. Kept so the download can finish.
+    replay_download: Option<ObjectUrl>
 }
 
 impl Component for GameDisplay {
@@ -533,175 +958,44 @@ impl Component for GameDisplay {
     type Properties = GameProps;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let mut piece_queue: VecDeque<TetrisPieceType> = VecDeque::from_iter(ctx.props().settings.randomizer.make_sequence(7).into_iter());
-        let first_piece = piece_queue.pop_front().unwrap_or(TetrisPieceType::I);
-        GameDisplay { game: TetrisBoard::make(10,20,first_piece), ticker_handle: None, move_handle: (true,None), 
-            down_handle: (true,None), settings: ctx.props().settings.clone(), level: 1, stick_handle: None, stick_counter: 0, held_piece: None, held_piece_switch_count: 0,
-            piece_queue, score: 0, lines_cleared: 0, game_end_screen: false,
-            touch_start_pos: (0,0), touch_pos: (0,0), touch_translation: 0, touch_velocity: (0,0), touch_can_rotate: true}
+        let link = ctx.link().clone();
+        let visibility_listener = EventListener::new(&document(), "visibilitychange", move |_| {
+            if document().hidden(){
+                link.send_message(GameMsg::Pause);
+            }
+        });
+        GameDisplay { game: Game::new(ctx.props().settings.clone()), ticker_handle: None, last_step: 0.0,
+            game_end_screen: false, new_best: false, section_bests: Vec::new(), announcement: None, touch_horiz_sens: ctx.props().touch_horiz_sens,
+            touch_start_pos: (0,0), touch_pos: (0,0), touch_translation: 0, touch_can_rotate: true, paused: false, _visibility_listener: visibility_listener,
+            playback: None, replay_playing: false, replay_speed: NORMAL_REPLAY_SPEED, replay_error: None, replay_reader: None, replay_download: None}
     }
 
     fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            GameMsg::Left(t) => {
-                if self.ticker_handle.is_none(){
-                    _ctx.link().send_message(GameMsg::Tick);
-                }
-                if self.move_handle.1.is_none() || t==InputTypes::Hold || self.move_handle.0 && self.move_handle.1.is_some(){
-                    self.game.move_left();
-                    if t!=InputTypes::Touch{
-                        let handle = if t==InputTypes::Hold{
-                            let link = _ctx.link().clone();
-                            Timeout::new(self.settings.hold_move_interval, move || link.send_message(GameMsg::Left(InputTypes::Hold)))
-                        } else {
-                            let link = _ctx.link().clone();
-                            Timeout::new(self.settings.hold_time, move || link.send_message(GameMsg::Left(InputTypes::Hold)))
-                        };
-                        self.move_handle = (false,Some(handle));
-                    }
-                    if self.stick_counter<self.settings.moves_before_lock{
-                        self.stick_handle=None;
-                    }
-                }
+            GameMsg::Input(input) => {
+                self.step(_ctx, &[input]);
             }
-            GameMsg::Right(t) => {
-                if self.ticker_handle.is_none(){
-                    _ctx.link().send_message(GameMsg::Tick);
-                }
-                if self.move_handle.1.is_none() || t==InputTypes::Hold || !self.move_handle.0 && self.move_handle.1.is_some(){
-                    self.game.move_right();
-                    if t!=InputTypes::Touch{
-                        let handle = if t==InputTypes::Hold{
-                            let link = _ctx.link().clone();
-                            Timeout::new(self.settings.hold_move_interval, move || link.send_message(GameMsg::Right(InputTypes::Hold)))
-                        } else {
-                            let link = _ctx.link().clone();
-                            Timeout::new(self.settings.hold_time, move || link.send_message(GameMsg::Right(InputTypes::Hold)))
-                        };
-                        self.move_handle = (true,Some(handle));
-
-                    }
-                    if self.stick_counter<self.settings.moves_before_lock{
-                        self.stick_handle=None;
-                    }
-                }
-            }
-            GameMsg::Down(t) => {
-                // if self.game.move_down(){
-                //     self.score+=1;
-                // }
-                if self.ticker_handle.is_none(){
-                    _ctx.link().send_message(GameMsg::Tick);
-                }
-                if self.down_handle.1.is_none() || t==InputTypes::Hold || self.down_handle.0 && self.down_handle.1.is_some(){
-                    if self.game.move_down(){
-                        self.score+=1;
-                    }
-                    if t!=InputTypes::Touch{
-                        let handle = if t==InputTypes::Hold{
-                            let link = _ctx.link().clone();
-                            Timeout::new(self.settings.hold_move_interval, move || link.send_message(GameMsg::Down(InputTypes::Hold)))
-                        } else {
-                            let link = _ctx.link().clone();
-                            Timeout::new(self.settings.hold_time, move || link.send_message(GameMsg::Down(InputTypes::Hold)))
-                        };
-                        self.down_handle = (false,Some(handle));
-                    }
-                    if self.stick_counter<self.settings.moves_before_lock{
-                        self.stick_handle=None;
-                    }
-                }
-            }
-            GameMsg::Drop => {
-                if self.ticker_handle.is_none(){
-                    _ctx.link().send_message(GameMsg::Tick);
-                }
-                if self.game_end_screen {return false}
-                self.score += self.game.drop()*2;
-                if !self.game.new_falling_piece(self.piece_queue.pop_front().unwrap_or(TetrisPieceType::I)){
-                    // reset game
-                    self.game_end_screen = true;
-                    self.ticker_handle=None;
-                    RootComponent::add_highscore(self.score);
-                    return true
-                }
-                if self.piece_queue.len()<=self.settings.queue_display_len{ self.piece_queue.extend(self.settings.randomizer.make_sequence(self.settings.queue_display_len))}
-                // self.piece_queue.push_back(TetrisPieceType::get_random());
-                let num_cleared: u32 = self.game.clear_lines();
-                self.score += [0,100,300,500,800][num_cleared as usize]*self.level;
-                self.lines_cleared+=num_cleared;
-                self.level=self.lines_cleared/10+1;
-                self.game.update_drop_loc();
-                self.stick_counter=0;
-                self.stick_handle=None;
-                self.held_piece_switch_count=0;
+            GameMsg::Tap(action) => {
+                self.step(_ctx, &[Input::Press(action), Input::Release(action)]);
             }
             GameMsg::Tick => {
-                if self.game_end_screen{
-                    self.game_end_screen = false;
-                    self.game = TetrisBoard::make(10,20,TetrisPieceType::get_random());
-                    self.level = 1;
-                    self.score = 0;
-                    self.lines_cleared=0;
-                    self.held_piece=None;
-                }
-                if !self.game.move_down(){
-                    if self.stick_handle.is_none(){
-                        self.stick_counter+=1;
-                        self.stick_handle = Some({
-                            let link = _ctx.link().clone();
-                            Timeout::new(self.settings.lock_delay, move || link.send_message(GameMsg::Drop))
-                        }); 
-                    }
-                }
-                let handle = {
-                    let link = _ctx.link().clone();
-                    Timeout::new(self.get_tick_speed(), move || link.send_message(GameMsg::Tick))
-                };
-                self.ticker_handle=Some(handle);
-            }
-            GameMsg::Hold => {
-                if self.ticker_handle.is_none(){
-                    _ctx.link().send_message(GameMsg::Tick);
-                }
-                if self.game_end_screen {return true}
-                self.held_piece_switch_count+=1;
-                if self.held_piece_switch_count>self.settings.max_num_held_piece_switches{
-                    return true
-                }
-                let curr_falling = self.game.falling_piece;
-                if self.held_piece.is_none(){
-                    self.game.new_falling_piece(self.piece_queue.pop_front().unwrap_or(TetrisPieceType::I));
+                if self.playback.is_some(){
+                    self.step_replay();
                 }else{
-                    self.game.new_falling_piece(self.held_piece.unwrap());
+                    self.step(_ctx, &[]);
                 }
-                self.game.update_drop_loc();
-                self.held_piece=Some(curr_falling);
-                self.stick_counter=0;
-                self.stick_handle=None;
             }
-            GameMsg::Rotate => {
-                if self.ticker_handle.is_none(){
-                    _ctx.link().send_message(GameMsg::Tick);
-                }
-                self.game.rotate_clockwise();
-                if self.stick_counter<self.settings.moves_before_lock{
-                    self.stick_handle=None;
-                }
-            }
-            GameMsg::CancelDown => {
-                if !self.down_handle.0{self.down_handle=(true,None);}
-            }
-            GameMsg::CancelLeft => {
-                if !self.move_handle.0{self.move_handle=(true,None);}
-            }
-            GameMsg::CancelRight => {
-                if self.move_handle.0{self.move_handle=(true,None);}
+            GameMsg::Restart => {
+                self.paused = false;
+                self.game_end_screen = false;
+                self.new_best = false;
+                self.section_bests.clear();
+                self.announcement = None;
+                self.game.restart();
+                self.step(_ctx, &[]);
             }
             GameMsg::TouchStart(t) => {
-                if self.ticker_handle.is_none(){
-                    _ctx.link().send_message(GameMsg::Tick);
-                }
+                self.step(_ctx, &[]);
                 let first_touch = t.touches().get(0).unwrap();
                 self.touch_start_pos=(first_touch.client_x(),first_touch.client_y());
                 self.touch_pos=self.touch_start_pos;
@@ -710,46 +1004,88 @@ impl Component for GameDisplay {
             GameMsg::TouchMove(t) => {
                 let first_touch = t.touches().get(0).unwrap();
                 let pos=(first_touch.client_x(),first_touch.client_y());
-                if pos.0-self.touch_translation>=self.settings.touch_horiz_sens { // && (pos.1-self.touch_start_pos.1).abs()<self.settings.touch_horiz_sens{
+                if pos.0-self.touch_translation>=self.touch_horiz_sens { // && (pos.1-self.touch_start_pos.1).abs()<self.touch_horiz_sens{
                     self.touch_translation=pos.0;
                     self.touch_can_rotate=false;
-                    _ctx.link().send_message(GameMsg::Right(InputTypes::Touch));
-                }else if pos.0-self.touch_translation<=-self.settings.touch_horiz_sens { //&& (pos.1-self.touch_start_pos.1).abs()<self.settings.touch_horiz_sens{
+                    _ctx.link().send_message(GameMsg::Tap(Action::Right));
+                }else if pos.0-self.touch_translation<=-self.touch_horiz_sens { //&& (pos.1-self.touch_start_pos.1).abs()<self.touch_horiz_sens{
                     self.touch_translation=pos.0;
                     self.touch_can_rotate=false;
-                    _ctx.link().send_message(GameMsg::Left(InputTypes::Touch));
+                    _ctx.link().send_message(GameMsg::Tap(Action::Left));
                 }
                 if pos.1-self.touch_start_pos.1>80{
                     self.touch_can_rotate=false;
-                    _ctx.link().send_message(GameMsg::Down(InputTypes::Touch));
+                    _ctx.link().send_message(GameMsg::Tap(Action::SoftDrop));
                 }
                 self.touch_pos=pos;
             }
             GameMsg::TouchEnd(t) => {
                 t.prevent_default();
                 if self.touch_pos.1-self.touch_start_pos.1>40{
-                    _ctx.link().send_message(GameMsg::Drop);
-                    // let handle = {
-                    //     let link = _ctx.link().clone();
-                    //     Timeout::new(0, move || link.send_message(GameMsg::Drop))
-                    // }.forget();
+                    _ctx.link().send_message(GameMsg::Input(Input::Press(Action::HardDrop)));
                 }else if self.touch_start_pos.1-self.touch_pos.1>40{
-                    _ctx.link().send_message(GameMsg::Hold);
-                    // let handle = {
-                    //     let link = _ctx.link().clone();
-                    //     Timeout::new(0, move || link.send_message(GameMsg::Hold))
-                    // }.forget();
+                    _ctx.link().send_message(GameMsg::Tap(Action::Hold));
                 }else if self.touch_can_rotate && (self.touch_pos.0-self.touch_start_pos.0).abs() < 5{
-                    _ctx.link().send_message(GameMsg::Rotate);
-                    // let handle = {
-                    //     let link = _ctx.link().clone();
-                    //     Timeout::new(0, move || link.send_message(GameMsg::Rotate))
-                    // }.forget();
+                    _ctx.link().send_message(GameMsg::Tap(Action::RotateClockwise));
                 }
                 self.touch_can_rotate=true;
             }
-            GameMsg::Unfocus => {
-                self.ticker_handle=None;
+            GameMsg::Pause => self.pause(_ctx),
+            GameMsg::Resume => {
+                if self.paused{
+                    self.paused = false;
+                    self.step(_ctx, &[]);
+                }
+            }
+            GameMsg::TogglePause => {
+                if self.playback.is_some(){
+                    _ctx.link().send_message(GameMsg::ToggleReplay);
+                }else if self.paused{
+                    _ctx.link().send_message(GameMsg::Resume);
+                }else{
+                    self.pause(_ctx);
+                }
+            }
+            GameMsg::Quit => {
+                self.paused = false;
+                self.game_end_screen = true;
+            }
+            GameMsg::WatchReplay => self.watch_replay(_ctx, self.game.replay()),
+            GameMsg::DownloadReplay => self.download_replay(),
+            GameMsg::OpenReplay(file) => {
+                if let Some(file) = file{
+                    let link = _ctx.link().clone();
+                    self.replay_reader = Some(read_as_bytes(&Blob::from(file), move |bytes| {
+                        link.send_message(GameMsg::ReplayRead(bytes.map_err(|e| e.to_string())));
+                    }));
+                }
+            }
+            GameMsg::ReplayRead(bytes) => {
+                self.replay_reader = None;
+                match bytes.and_then(|bytes| Replay::decode(&bytes)){
+                    Ok(replay) => self.watch_replay(_ctx, replay),
+                    Err(e) => self.replay_error = Some(e)
+                }
+            }
+            GameMsg::ToggleReplay => {
+                if self.replay_playing{
+                    self.replay_playing = false;
+                    self.ticker_handle = None;
+                }else{
+                    self.play_replay(_ctx);
+                }
+            }
+            GameMsg::SeekReplay(time) => {
+                if let Some(playback) = self.playback.as_mut(){
+                    playback.seek(time);
+                    self.last_step = now();
+                }
+            }
+            GameMsg::ChangeReplaySpeed => self.replay_speed = (self.replay_speed+1)%REPLAY_SPEEDS.len(),
+            GameMsg::CloseReplay => {
+                self.playback = None;
+                self.replay_playing = false;
+                self.ticker_handle = None;
             }
             GameMsg::None => {
 
@@ -759,42 +1095,218 @@ impl Component for GameDisplay {
     }
     fn view(&self, ctx: &Context<Self>) -> Html {
         let link = ctx.link();
-        let dim = match window(){
-            Some(w) => (w.outer_width().unwrap().as_f64().unwrap(),w.outer_height().unwrap().as_f64().unwrap()),
-            None => (0.0,0.0)
-        };
+        let game = self.playback.as_ref().map_or(&self.game, |p| p.game());
         html!{
-            <div class="game no-touch-move" tabindex=0 onkeydown={link.callback(|key:KeyboardEvent| {match key.key_code(){67=>GameMsg::Hold,40=>GameMsg::Down(InputTypes::Tap), 39=>GameMsg::Right(InputTypes::Tap), 38=>GameMsg::Rotate, 37=>GameMsg::Left(InputTypes::Tap), 32 =>GameMsg::Drop,_=>GameMsg::None}})}
-            onkeyup={link.callback(|key:KeyboardEvent| {match key.key_code(){40=>GameMsg::CancelDown, 39=>GameMsg::CancelRight, 37=>GameMsg::CancelLeft, 27=>GameMsg::Unfocus, _=>GameMsg::None}})}
-            onfocusout={link.callback(|_| GameMsg::Unfocus)}> //onfocusin={link.callback(|_| GameMsg::Tick)} 
-                <div class="inline-block" onclick={link.callback(|_| GameMsg::Hold)}>
-                    {TetrisPieceType::view(&self.held_piece)}
+            <div class={classes!("game", "no-touch-move", (self.paused && self.playback.is_none()).then_some("paused"))} tabindex=0 style={format!("--board-width: {}; --board-height: {};", game.board().dimentions().0, game.board().visible_height())} onkeydown={link.callback(|key:KeyboardEvent| {if key.repeat() {return GameMsg::None} match key.key_code(){67=>GameMsg::Input(Input::Press(Action::Hold)),40=>GameMsg::Input(Input::Press(Action::SoftDrop)), 39=>GameMsg::Input(Input::Press(Action::Right)),
+                38=>GameMsg::Input(Input::Press(Action::RotateClockwise)), 90=>GameMsg::Input(Input::Press(Action::RotateCounterClockwise)), 65=>GameMsg::Input(Input::Press(Action::Rotate180)), 37=>GameMsg::Input(Input::Press(Action::Left)), 32 =>GameMsg::Input(Input::Press(Action::HardDrop)),_=>GameMsg::None}})}
+            onkeyup={link.callback(|key:KeyboardEvent| {match key.key_code(){40=>GameMsg::Input(Input::Release(Action::SoftDrop)), 39=>GameMsg::Input(Input::Release(Action::Right)), 37=>GameMsg::Input(Input::Release(Action::Left)), 27=>GameMsg::TogglePause,
+                67=>GameMsg::Input(Input::Release(Action::Hold)), 38=>GameMsg::Input(Input::Release(Action::RotateClockwise)), 90=>GameMsg::Input(Input::Release(Action::RotateCounterClockwise)), 65=>GameMsg::Input(Input::Release(Action::Rotate180)), _=>GameMsg::None}})}
+            onfocusout={link.callback(|_| GameMsg::Pause)}> //onfocusin={link.callback(|_| GameMsg::Tick)} 
+                <div class="inline-block" onclick={link.callback(|_| GameMsg::Tap(Action::Hold))}>
+                    {view_piece(&game.held_piece(), game.board().rotation_system())}
+                    if let GameModes::Sprint{lines} = game.settings().mode{
+                        <div class="sidebar-num-display">
+                        <h1>{"Time"}</h1>
+                        <p>{format_time(game.elapsed_ms())}</p>
+                        </div>
+                        <div class="sidebar-num-display">
+                        <h1>{"Lines"}</h1>
+                        <p>{format!("{}/{}", game.lines_cleared().min(lines), lines)}</p>
+                        </div>
+                        if let Some(split) = game.splits().last(){
+                            <div class="sidebar-num-display">
+                            <h1>{format!("{}L split", game.splits().len()*10)}</h1>
+                            <p>{format_time(*split)}</p>
+                            </div>
+                        }
+                    }else if let GameModes::Dig{..} = game.settings().mode{
+                        <div class="sidebar-num-display">
+                        <h1>{"Time"}</h1>
+                        <p>{format_time(game.elapsed_ms())}</p>
+                        </div>
+                        <div class="sidebar-num-display">
+                        <h1>{"Garbage"}</h1>
+                        <p>{game.garbage_remaining().to_string()}</p>
+                        </div>
+                    }else if let GameModes::Survival{..} = game.settings().mode{
+                        <div class="sidebar-num-display">
+                        <h1>{"Time"}</h1>
+                        <p>{format_time(game.elapsed_ms())}</p>
+                        </div>
+                        <div class="sidebar-num-display">
+                        <h1>{"Lines"}</h1>
+                        <p>{game.lines_cleared().to_string()}</p>
+                        </div>
+                        <div class="sidebar-num-display">
+                        <h1>{"Rising in"}</h1>
+                        <p>{format_time(game.next_garbage_in().unwrap_or(0) as u64)}</p>
+                        </div>
+                    }else if let GameModes::Master = game.settings().mode{
+                        <div class="sidebar-num-display">
+                        <h1>{"Grade"}</h1>
+                        <p>{game.grade().to_string()}</p>
+                        </div>
+                        <div class="sidebar-num-display">
+                        <h1>{"Level"}</h1>
+                        <p>{format!("{}/{}", game.master_level(), ((game.master_level()/master::SECTION_LEVELS+1)*master::SECTION_LEVELS).min(master::MAX_LEVEL))}</p>
+                        </div>
+                        <div class="sidebar-num-display">
+                        <h1>{"Time"}</h1>
+                        <p>{format_time(game.elapsed_ms())}</p>
+                        </div>
+                    }else if let GameModes::Ultra{duration_ms} = game.settings().mode{
+                        <div class="sidebar-num-display">
+                        <h1>{"Time left"}</h1>
+                        <p>{format_time((duration_ms as u64).saturating_sub(game.elapsed_ms()))}</p>
+                        </div>
+                        <div class="sidebar-num-display">
+                        <h1>{"Score"}</h1>
+                        <p>{game.score().to_string()}</p>
+                        </div>
+                    }else{
+                        <div class="sidebar-num-display">
+                        <h1>{"Score"}</h1>
+                        <p>{game.score().to_string()}</p>
+                        </div>
+                        <div class="sidebar-num-display">
+                        <h1>{"Level"}</h1>
+                        if game.settings().mode==(GameModes::Marathon{endless: false}){
+                            <p>{format!("{}/{}", game.level().min(MARATHON_LINES/10), MARATHON_LINES/10)}</p>
+                        }else{
+                            <p>{game.level().to_string()}</p>
+                        }
+                        </div>
+                    }
                     <div class="sidebar-num-display">
-                    <h1>{"Score"}</h1>
-                    <p>{self.score.to_string()}</p>
+                    <h1>{"Combo"}</h1>
+                    <p>{game.combo().to_string()}</p>
                     </div>
                     <div class="sidebar-num-display">
-                    <h1>{"Level"}</h1>
-                    <p>{self.level.to_string()}</p>
+                    <h1>{"B2B"}</h1>
+                    <p>{game.back_to_back().to_string()}</p>
                     </div>
+                    if let Some((text, _)) = self.announcement.as_ref().filter(|(_, at)| now()-at<ANNOUNCEMENT_MS){
+                        <div class="sidebar-num-display announcement">
+                        <p>{text.clone()}</p>
+                        </div>
+                    }
                 </div>
-                <div class="inline-block" ontouchstart={link.callback(|t:TouchEvent| GameMsg::TouchStart(t))} ontouchmove={link.callback(|t| GameMsg::TouchMove(t))} ontouchend={link.callback(|t| GameMsg::TouchEnd(t))}>
-                    {self.game.view()}
+                <div class="inline-block" ontouchstart={link.callback(GameMsg::TouchStart)} ontouchmove={link.callback(GameMsg::TouchMove)} ontouchend={link.callback(GameMsg::TouchEnd)}>
+                    {view_board(game.board())}
                 </div>
                 <div class="inline-block">
                 {
-                    (0..self.settings.queue_display_len).map(|v|{
-                        html!{TetrisPieceType::view(&Some(self.piece_queue[v].clone()))}
+                    game.piece_queue().iter().take(game.settings().queue_display_len).map(|p|{
+                        html!{view_piece(&Some(*p), game.board().rotation_system())}
                     }).collect::<Html>()
                 }
                 </div>
 
-                if self.game_end_screen{
-                    <div class="notouch" onclick={link.callback(|_| GameMsg::Tick)}></div>
+                if let Some(playback) = &self.playback{
+                    <div class="replay-controls">
+                        <button onclick={link.callback(|_| GameMsg::ToggleReplay)}>{if self.replay_playing {"Pause"} else {"Play"}}</button>
+                        <input type="range" min="0" max={playback.replay().length.to_string()} value={playback.time().to_string()}
+                            oninput={link.callback(|e: InputEvent| {let input: HtmlInputElement = e.target_unchecked_into(); GameMsg::SeekReplay(input.value().parse().unwrap_or(0))})}/>
+                        <span>{format!("{} / {}", format_time(playback.time()), format_time(playback.replay().length))}</span>
+                        <button onclick={link.callback(|_| GameMsg::ChangeReplaySpeed)}>{format!("{}x", REPLAY_SPEEDS[self.replay_speed])}</button>
+                        <button onclick={link.callback(|_| GameMsg::DownloadReplay)}>{"Download"}</button>
+                        <button onclick={link.callback(|_| GameMsg::CloseReplay)}>{"Close"}</button>
+                    </div>
+                }else if self.paused{
+                    <div class="notouch"></div>
+                    <div class="game-end-menu">
+                        <h1>{"Paused"}</h1>
+                        <button onclick={link.callback(|_| GameMsg::Resume)}>{"Resume"}</button>
+                        <button onclick={link.callback(|_| GameMsg::Restart)}>{"Restart"}</button>
+                        <button onclick={link.callback(|_| GameMsg::Quit)}>{"Quit"}</button>
+                        {self.view_replay_buttons(link)}
+                    </div>
+                }else if self.game_end_screen && game.settings().mode==GameModes::Master{
+                    <div class="notouch" onclick={link.callback(|_| GameMsg::Restart)}></div>
+                    <div class="game-end-menu">
+                        <h1>{format!("Grade {}", game.grade())}</h1>
+                        if self.new_best{
+                            <h2>{"New best grade!"}</h2>
+                        }
+                        <h2>{format!("Level: {}",game.master_level())}</h2>
+                        <h2>{format!("Time: {}",format_time(game.elapsed_ms()))}</h2>
+                        {
+                            section_durations(game.section_times()).into_iter().enumerate().map(|(i, t)| {
+                                let best = if self.section_bests.get(i)==Some(&true) {" (best)"} else {""};
+                                html!{<p>{format!("{}: {}{}", section_name(i), format_time(t), best)}</p>}
+                            }).collect::<Html>()
+                        }
+                        <p>{format!("Seed: {}",game.seed())}</p>
+                        {self.view_replay_buttons(link)}
+                    </div>
+                }else if self.game_end_screen && matches!(game.settings().mode, GameModes::Survival{..}){
+                    <div class="notouch" onclick={link.callback(|_| GameMsg::Restart)}></div>
+                    <div class="game-end-menu">
+                        <h1>{"Survived"}</h1>
+                        <h2>{format!("Time: {}",format_time(game.elapsed_ms()))}</h2>
+                        if self.new_best{
+                            <h2>{"New personal best!"}</h2>
+                        }
+                        <h2>{format!("Lines: {}",game.lines_cleared())}</h2>
+                        <p>{format!("Seed: {}",game.seed())}</p>
+                        {self.view_replay_buttons(link)}
+                    </div>
+                }else if self.game_end_screen && game.is_finished() && matches!(game.settings().mode, GameModes::Ultra{..}){
+                    <div class="notouch" onclick={link.callback(|_| GameMsg::Restart)}></div>
+                    <div class="game-end-menu">
+                        <h1>{"Time's Up"}</h1>
+                        <h2>{format!("Score: {}",game.score())}</h2>
+                        if self.new_best{
+                            <h2>{"New best score!"}</h2>
+                        }
+                        <h2>{format!("Lines: {}",game.lines_cleared())}</h2>
+                        <h2>{format!("Max combo: {}",game.max_combo())}</h2>
+                        <p>{format!("Seed: {}",game.seed())}</p>
+                        {self.view_replay_buttons(link)}
+                    </div>
+                }else if self.game_end_screen && game.is_finished() && matches!(game.settings().mode, GameModes::Marathon{..}){
+                    <div class="notouch" onclick={link.callback(|_| GameMsg::Restart)}></div>
+                    <div class="game-end-menu">
+                        <h1>{"Victory"}</h1>
+                        <h2>{format!("Score: {}",game.score())}</h2>
+                        <h2>{format!("Time: {}",format_time(game.elapsed_ms()))}</h2>
+                        <h2>{format!("Max combo: {}",game.max_combo())}</h2>
+                        <h2>{format!("Max B2B: {}",game.max_back_to_back())}</h2>
+                        <h2>{format!("Perfect clears: {}",game.perfect_clears())}</h2>
+                        <p>{format!("Seed: {}",game.seed())}</p>
+                        {self.view_replay_buttons(link)}
+                    </div>
+                }else if self.game_end_screen && game.is_finished(){
+                    <div class="notouch" onclick={link.callback(|_| GameMsg::Restart)}></div>
+                    <div class="game-end-menu">
+                        <h1>{"Finished"}</h1>
+                        <h2>{format!("Time: {}",format_time(game.elapsed_ms()))}</h2>
+                        if self.new_best{
+                            <h2>{"New personal best!"}</h2>
+                        }
+                        if let GameModes::Sprint{..} = game.settings().mode{
+                            {
+                                game.splits().iter().enumerate().map(|(i, t)| {
+                                    html!{<p>{format!("{}L: {}", (i+1)*10, format_time(*t))}</p>}
+                                }).collect::<Html>()
+                            }
+                        }else{
+                            <h2>{format!("Pieces: {}",game.pieces_placed())}</h2>
+                        }
+                        <p>{format!("Seed: {}",game.seed())}</p>
+                        {self.view_replay_buttons(link)}
+                    </div>
+                }else if self.game_end_screen{
+                    <div class="notouch" onclick={link.callback(|_| GameMsg::Restart)}></div>
                     <div class="game-end-menu">
                         <h1>{"Game Over"}</h1>
-                        <h2>{format!("Score: {}",self.score)}</h2>
-                        <h2>{format!("Level: {}",self.level)}</h2>
+                        <h2>{format!("Score: {}",game.score())}</h2>
+                        <h2>{format!("Level: {}",game.level())}</h2>
+                        <h2>{format!("Max combo: {}",game.max_combo())}</h2>
+                        <h2>{format!("Max B2B: {}",game.max_back_to_back())}</h2>
+                        <h2>{format!("Perfect clears: {}",game.perfect_clears())}</h2>
+                        <p>{format!("Seed: {}",game.seed())}</p>
+                        {self.view_replay_buttons(link)}
                     </div>
                 }
 
@@ -802,445 +1314,279 @@ impl Component for GameDisplay {
         }
     }
 }
+
 impl GameDisplay{
-    fn get_tick_speed(&self) -> u32{
-        return ((0.8-((self.level-1) as f32)*0.007).powf((self.level-1) as f32)*1000_f32) as u32
+    /// Stops a running game until it is resumed.
+    fn pause(&mut self, ctx: &Context<Self>){
+        if self.ticker_handle.is_none() || self.paused || self.playback.is_some() {return}
+        // keyup events are lost once focus is gone, so let go of everything before stopping
+        self.step(ctx, &[Input::Release(Action::Left), Input::Release(Action::Right), Input::Release(Action::SoftDrop), Input::Release(Action::Hold),
+            Input::Release(Action::RotateClockwise), Input::Release(Action::RotateCounterClockwise), Input::Release(Action::Rotate180)]);
+        self.ticker_handle=None;
+        self.paused = !self.game_end_screen;
     }
-}
-
-#[derive(PartialEq, Clone)]
-struct Settings{
-    hold_time: u32,
-    hold_move_interval: u32,
-    down_hold_time: u32,
-    down_hold_move_interval: u32,
-    max_num_held_piece_switches: u32,
-    queue_display_len: usize,
-    lock_delay: u32,
-    moves_before_lock: u32,
-    randomizer: Randomizers,
-    touch_horiz_sens: i32
-}
-impl Default for Settings{
-    fn default() -> Settings{
-        Settings{hold_time: 150, hold_move_interval: 60, max_num_held_piece_switches: 1, queue_display_len: 4, lock_delay: 500, moves_before_lock: 15, randomizer: Randomizers::RandomGenerator,
-        touch_horiz_sens: 25, down_hold_time: 50, down_hold_move_interval: 50}
+    /// Saves the grade and section times of a Master game that just ended.
+    fn record_master(&mut self){
+        self.new_best = RootComponent::add_record(MASTER_GRADE_COOKIE, self.game.grade().rank() as u64, false);
+        self.section_bests = RootComponent::add_section_times(self.game.section_times());
     }
-}
-
-#[derive(PartialEq, Clone)]
-enum Randomizers{
-    RandomGenerator,
-    Random
-}
-impl Randomizers{
-    fn make_sequence(&self, len: usize) -> Vec<TetrisPieceType>{
-        match &self{
-            Self::RandomGenerator => {
-                let mut temp = (0..(((len-1)/7+1)*7)).map(|i| TetrisPieceType::from_int((i%7) as i32)).collect::<Vec<TetrisPieceType>>();
-                for i in 0..temp.len(){
-                    let swap_idx = rand::thread_rng().gen_range(0..7)+(i/7)*7;
-                    let tempt = temp[swap_idx];
-                    temp[swap_idx]=temp[i].clone();
-                    temp[i]=tempt;
-                }
-                temp
-            }
-            Self::Random => {
-                Vec::from_iter((0..len).map(|_| TetrisPieceType::get_random()).collect::<Vec<TetrisPieceType>>())
-            }
+    /// Steps the game up to the current time with `inputs`, starting the frame ticker if it was stopped.
+    fn step(&mut self, ctx: &Context<Self>, inputs: &[Input]){
+        if self.paused || self.playback.is_some() {return}
+        if self.game_end_screen{
+            // any key press on the game over screen starts a new game
+            if !inputs.iter().any(|i| matches!(i, Input::Press(_))) {return}
+            self.game_end_screen = false;
+            self.new_best = false;
+            self.section_bests.clear();
+            self.announcement = None;
+            self.game.restart();
         }
-    }
-}
-impl ToString for Randomizers{
-    fn to_string(&self) -> String {
-        match &self{
-            Self::RandomGenerator => String::from("RandomGenerator"),
-            Self::Random => String::from("Random")
+        let now = now();
+        if self.ticker_handle.is_none(){
+            // time spent stopped doesn't count towards gravity or lock delay
+            self.last_step = now;
+            let link = ctx.link().clone();
+            self.ticker_handle = Some(Interval::new(FRAME_MS, move || link.send_message(GameMsg::Tick)));
         }
-    }
-}
-
-#[derive(Clone,Copy, PartialEq)]
-enum TetrisPieceType{
-    I,
-    L,
-    J,
-    O,
-    S,
-    T,
-    Z
-}
-impl TetrisPieceType{
-    fn get_idx_arr(&self, rot: usize) -> [isize;4]{
-        match rot%4{
-            2 => match &self{
-                Self::I => [4,5,6,7],
-                Self::L =>  [0,4,5,6],
-                Self::J => [2,4,5,6],
-                Self::O => [1,2,5,6],
-                Self::Z => [1,2,4,5],
-                Self::T => [1,4,5,6],
-                Self::S => [0,1,5,6],
-            }
-            1 => match &self{
-                Self::I => [2,6,10,14],
-                Self::L =>  [1,2,5,9],
-                Self::J => [1,5,9,10],
-                Self::O => [1,2,5,6],
-                Self::S => [2,5,6,9],
-                Self::T => [1,5,6,9],
-                Self::Z => [1,5,6,10],
-            }
-            0 => match &self{
-                Self::I => [8,9,10,11],
-                Self::L =>  [4,5,6,10],
-                Self::J => [4,5,6,8],
-                Self::O => [1,2,5,6],
-                Self::S => [4,5,9,10],
-                Self::T => [4,5,6,9],
-                Self::Z => [5,6,8,9],
-            }
-            3 => match &self{
-                Self::I => [1,5,9,13],
-                Self::L =>  [1,5,8,9],
-                Self::J => [0,1,5,9],
-                Self::O => [1,2,5,6],
-                Self::S => [1,4,5,8],
-                Self::T => [1,4,5,9],
-                Self::Z => [0,4,5,9],
+        let elapsed = (now-self.last_step).max(0.0) as u32;
+        self.last_step += elapsed as f64;
+        // a tetris is only announced if nothing more special happened with the same piece
+        let mut announced = false;
+        for event in self.game.step(elapsed, inputs){
+            match event{
+                GameEvent::GameOver => {
+                    self.game_end_screen = true;
+                    self.ticker_handle=None;
+                    match self.game.settings().mode{
+                        GameModes::Marathon{..} => RootComponent::add_highscore(self.game.score()),
+                        GameModes::Survival{interval_ms} => self.new_best = RootComponent::add_record(&survival_best_cookie(interval_ms/1000), self.game.elapsed_ms(), false),
+                        GameModes::Master => self.record_master(),
+                        _ => {}
+                    }
+                }
+                GameEvent::Finished => {
+                    self.game_end_screen = true;
+                    self.ticker_handle=None;
+                    match self.game.settings().mode{
+                        GameModes::Sprint{lines} => self.new_best = RootComponent::add_record(&format!("sprint_best_{}", lines), self.game.elapsed_ms(), true),
+                        GameModes::Ultra{duration_ms} => self.new_best = RootComponent::add_record(&ultra_best_cookie(duration_ms/1000), self.game.score() as u64, false),
+                        GameModes::Marathon{..} => RootComponent::add_highscore(self.game.score()),
+                        GameModes::Dig{lines, ..} => self.new_best = RootComponent::add_record(&format!("dig_best_{}", lines), self.game.elapsed_ms(), true),
+                        GameModes::Master => self.record_master(),
+                        GameModes::Zen | GameModes::Survival{..} => {}
+                    }
+                }
+                GameEvent::TSpin{kind, lines_cleared} => {
+                    let b2b = if lines_cleared>0 && self.game.back_to_back()>0 {"B2B "} else {""};
+                    self.announcement = Some((format!("{}{}{}", b2b, kind, clear_name(lines_cleared)), now));
+                    announced = true;
+                }
+                GameEvent::PerfectClear{lines_cleared} => {
+                    self.announcement = Some((format!("Perfect Clear{}", clear_name(lines_cleared)), now));
+                    announced = true;
+                }
+                GameEvent::PieceLocked{lines_cleared} => {
+                    if lines_cleared==4 && !announced{
+                        let b2b = if self.game.back_to_back()>0 {"B2B "} else {""};
+                        self.announcement = Some((format!("{}Tetris", b2b), now));
+                    }
+                    announced = false;
+                }
             }
-            _ => [0,0,0,0]
         }
     }
-    fn horiz_extents(&self, rot: usize) -> (isize,isize){
-        match rot%4{
-            2 => match &self{
-                Self::I => (0,4),
-                Self::L|Self::J|Self::Z|Self::T|Self::S =>  (0,3),
-                Self::O => (1,3),
-            }
-            1 => match &self{
-                Self::I => (2,3),
-                Self::L|Self::J|Self::O|Self::S|Self::T|Self::Z =>  (1,3),
-            }
-            0 => match &self{
-                Self::I => (0,4),
-                Self::L|Self::J|Self::S|Self::T|Self::Z =>  (0,3),
-                Self::O => (1,3),
-            }
-            3 => match &self{
-                Self::I => (1,2),
-                Self::L|Self::J|Self::S|Self::T|Self::Z =>  (0,2),
-                Self::O => (1,3),
+    /// Buttons to watch, save and open replays, for the pause and game over menus.
+    fn view_replay_buttons(&self, link: &yew::html::Scope<Self>) -> Html{
+        html!{
+            <>
+            <button onclick={link.callback(|_| GameMsg::WatchReplay)}>{"Watch replay"}</button>
+            <button onclick={link.callback(|_| GameMsg::DownloadReplay)}>{"Download replay"}</button>
+            <label class="replay-open">{"Open replay"}
+                <input type="file" accept=".ttr,.json" onchange={link.callback(|e: Event| {let input: HtmlInputElement = e.target_unchecked_into(); GameMsg::OpenReplay(input.files().and_then(|f| f.get(0)))})}/>
+            </label>
+            if let Some(error) = &self.replay_error{
+                <p>{error.clone()}</p>
             }
-            _ => (0,0)
+            </>
         }
     }
-    fn vert_extents(&self, rot: usize) -> (isize,isize){
-        match rot%4{
-            1 => match &self{
-                Self::I => (0,4),
-                Self::L|Self::J|Self::S|Self::T|Self::Z =>  (0,3),
-                Self::O => (0,2),
-            }
-            0 => match &self{
-                Self::I => (2,3),
-                Self::L|Self::J|Self::S|Self::T|Self::Z =>  (1,3),
-                Self::O => (0,2),
-            }
-            3 => match &self{
-                Self::I => (0,4),
-                Self::L|Self::J|Self::S|Self::T|Self::Z =>  (0,3),
-                Self::O => (0,2),
-            }
-            2 => match &self{
-                Self::I => (1,2),
-                Self::L|Self::J|Self::S|Self::T|Self::Z|Self::O =>  (0,2),
+    /// Starts watching `replay` from the beginning in place of the game, which waits where it is.
+    fn watch_replay(&mut self, ctx: &Context<Self>, replay: Replay){
+        self.replay_error = None;
+        self.playback = Some(Playback::new(replay));
+        self.play_replay(ctx);
+    }
+    /// Plays the replay being watched from where it is, or from the start if it already ended.
+    fn play_replay(&mut self, ctx: &Context<Self>){
+        if let Some(playback) = self.playback.as_mut(){
+            if playback.is_done(){
+                playback.seek(0);
             }
-            _ => (0,0)
+            self.replay_playing = true;
+            self.last_step = now();
+            let link = ctx.link().clone();
+            self.ticker_handle = Some(Interval::new(FRAME_MS, move || link.send_message(GameMsg::Tick)));
         }
     }
-    fn secondary_tests(&self, rot: usize) -> Vec<(isize,isize)>{
-        match rot%4{
-            1 => match &self{
-                Self::I => vec![(-2, 0),(1, 0),(-2,-1),(1,2)],
-                Self::O => vec![],
-                Self::J|Self::L|Self::S|Self::T|Self::Z => vec![(-1, 0),(-1,1),(0,-2),(-1,-2)]
-            }
-            0 => match &self{
-                Self::I => vec![( 0, 0),(1, 0),(-2, 0),(1,-2),(-2,1)],
-                Self::O => vec![],
-                Self::J|Self::L|Self::S|Self::T|Self::Z => vec![( 0, 0),(-1, 0),(-1,-1),( 0,2),(-1,2)]
-            }
-            3 => match &self{
-                Self::I => vec![( 0, 0),(2, 0),(-1, 0),(2,1),(-1,-2)],
-                Self::O => vec![],
-                Self::J|Self::L|Self::S|Self::T|Self::Z => vec![( 0, 0),(1, 0),(1,1),( 0,-2),(1,-2)]
+    /// Plays the replay being watched on by the time since the last frame, at the replay speed.
+    fn step_replay(&mut self){
+        let speed = REPLAY_SPEEDS[self.replay_speed];
+        let elapsed = ((now()-self.last_step).max(0.0)*speed) as u64;
+        self.last_step += elapsed as f64/speed;
+        if let Some(playback) = self.playback.as_mut(){
+            playback.advance(elapsed);
+            if playback.is_done(){
+                self.replay_playing = false;
+                self.ticker_handle = None;
             }
-            2 => match &self{
-                Self::I => vec![( 0, 0),(-1, 0),(2, 0),(-1,2),(2,-1)],
-                Self::O => vec![],
-                Self::J|Self::L|Self::S|Self::T|Self::Z => vec![( 0, 0),(1, 0),(1,-1),( 0,2),(1,2)]
-            }
-            _ => vec![]
         }
     }
-    fn from_int(val: i32) -> Self{
-        match val%7{
-            0 => Self::I,
-            1 => Self::J,
-            2 => Self::L,
-            3 => Self::O, 
-            4 => Self::S,
-            5 => Self::T,
-            6 => Self::Z,
-            _ => Self::T
+    /// Saves the replay being watched, or else the one of the current game, through the browser's downloads.
+    fn download_replay(&mut self){
+        let replay = self.playback.as_ref().map_or_else(|| self.game.replay(), |p| p.replay().clone());
+        let name = format!("tetris-{}-{}.ttr", mode_name(&replay.settings.mode).to_lowercase(), replay.settings.seed.unwrap_or(0));
+        let url = ObjectUrl::from(Blob::new_with_options(replay.to_bytes().as_slice(), Some("application/octet-stream")));
+        if let Ok(a) = document().create_element("a"){
+            let _ = a.set_attribute("href", &url);
+            let _ = a.set_attribute("download", &name);
+            a.unchecked_into::<HtmlElement>().click();
         }
-    }
-    fn get_random() -> Self{
-        TetrisPieceType::from_int(rand::thread_rng().gen_range(0..7))
+        self.replay_download = Some(url);
     }
 }
 
-impl TetrisPieceType{
-    fn view(from: &Option<Self>) -> Html{
-        html!{
-            <div class="piece-display">
-                {
-                    (0..4).rev().map(|r|{
-                        html!{
-                            {
-                                (0..4).map(|c| {
-                                    html!{
-                                        if from.is_some() && from.unwrap().get_idx_arr(0).contains(&((c+r*4) as isize)){
-                                            <span class={format!("sidebar-tile filled {}-color",from.unwrap())}/>
-                                        }else{
-                                            <span class="sidebar-tile empty"/>
-                                        }
-                                    }
-                                }).collect::<Html>()
-                            }
-                        }
-                    }).collect::<Html>()
-                }
-            </div>
-        }
-    }
+fn now() -> f64{
+    window().and_then(|w| w.performance()).map(|p| p.now()).unwrap_or(0.0)
 }
-impl Display for TetrisPieceType{
-    fn fmt(&self, f: &mut Formatter) -> Result {
-        write!(f,"{}",String::from(match self{
-            Self::I => "I",
-            Self::J => "J",
-            Self::L => "L",
-            Self::O => "O",
-            Self::S => "S",
-            Self::T => "T",
-            Self::Z => "Z"
-        }))
-    }
+
+/// Reads a seed from the settings: a number, or nothing for a new one every game. None if it is neither.
+fn parse_seed(value: &str) -> Option<Option<u64>>{
+    let value = value.trim();
+    if value.is_empty() {Some(None)} else {value.parse::<u64>().ok().map(Some)}
 }
 
-struct TetrisBoard{
-    tiles: Vec<Option<TetrisPieceType>>,
-    dimentions: (isize, isize),
-    falling_piece: TetrisPieceType,
-    falling_loc: isize,
-    falling_rot: usize,
-    drop_loc: isize
+/// A time in milliseconds as minutes, seconds and milliseconds.
+fn format_time(ms: u64) -> String{
+    format!("{}:{:02}.{:03}", ms/60000, ms/1000%60, ms%1000)
 }
 
-impl TetrisBoard{
-    fn make(width: usize, height: usize, first_falling_piece: TetrisPieceType) -> Self{
-        let mut tiles = vec![None;width*(height+3)];
-        // tiles[0]=true;
-        Self{tiles, dimentions: (width as isize, (height+3) as isize), falling_piece:first_falling_piece, 
-        falling_loc: 193, falling_rot:0, drop_loc: -7}
+/// Name of a mode without its parameters, for the settings button and cookie.
+fn mode_name(mode: &GameModes) -> &'static str{
+    match mode{
+        GameModes::Marathon{..} => "Marathon",
+        GameModes::Sprint{..} => "Sprint",
+        GameModes::Ultra{..} => "Ultra",
+        GameModes::Zen => "Zen",
+        GameModes::Dig{..} => "Dig",
+        GameModes::Survival{..} => "Survival",
+        GameModes::Master => "Master"
     }
-    
-    fn check_loc_for_falling_piece(&self, idx: isize) -> bool{
-        for i in self.falling_piece.get_idx_arr(self.falling_rot){
-            if self.falling_loc<self.dimentions.0*self.dimentions.1 && self.falling_loc+i/4*self.dimentions.0+i%4==idx{ return true }
-        }
-        return false
-    }
-    fn check_drop_loc(&self, idx: isize) -> bool{
-        for i in self.falling_piece.get_idx_arr(self.falling_rot){
-            if self.drop_loc<self.dimentions.0*self.dimentions.1 && self.drop_loc+i/4*self.dimentions.0+i%4==idx{ return true }
-        }
-        return false
-    }
-    fn move_down(&mut self) -> bool{
-        // if self.falling_loc>=self.dimentions.0{
-        //     self.falling_loc-=self.dimentions.0;
-        //     if self.check_overlap(){
-        //         self.falling_loc+=self.dimentions.0;
-        //     }
-        // }
-        self.falling_loc-=self.dimentions.0;
-        if !self.check_in_bounds() || self.check_overlap(){
-            self.falling_loc+=self.dimentions.0;
-            return false
-        }
-        return true
-    }
-    fn move_right(&mut self){
-        // if self.falling_loc%self.dimentions.0!=(self.dimentions.0-self.falling_piece.horiz_extents(self.falling_rot).1)%self.dimentions.0{
-        //     self.falling_loc+=1;
-        //     if self.check_overlap(){
-        //         self.falling_loc-=1;
-        //     }
-        // }
-        if !(self.falling_piece==TetrisPieceType::I&&(self.falling_loc+self.falling_piece.horiz_extents(self.falling_rot).0)%self.dimentions.0==9){
-            self.falling_loc+=1;
-        }
-        if !self.check_in_bounds() || self.check_overlap(){
-            self.falling_loc-=1;
-        }else{
-            self.update_drop_loc();
-        }
-    }
-    fn move_left(&mut self){
-        /*
-        if (self.falling_loc%self.dimentions.0) != (self.dimentions.0-self.falling_piece.horiz_extents(self.falling_rot).0)%self.dimentions.0{
-            self.falling_loc-=1;
-            if self.check_overlap(){
-                self.falling_loc+=1;
-            }
-        }*/
-        if !(self.falling_piece==TetrisPieceType::I&&(self.falling_loc+self.falling_piece.horiz_extents(self.falling_rot).0)%self.dimentions.0==0){
-            self.falling_loc-=1;
-        }
-        if !self.check_in_bounds() || self.check_overlap(){
-            self.falling_loc+=1;
-        }else{
-            self.update_drop_loc();
-        }
-    }
-    fn clear_lines(&mut self) -> u32{
-        let mut line_counter = 0;
-        for r in 0..self.dimentions.1{
-            let mut filled: bool = true;
-            for c in 0..self.dimentions.0{
-                if self.tiles[(r*self.dimentions.0+c) as usize].is_none(){
-                    filled=false;
-                }
-            }
-            if filled{
-                line_counter+=1;
-                for c in 0..self.dimentions.0{
-                    self.tiles[(r*self.dimentions.0+c) as usize]=None;
-                }
-            }else{
-                for c in 0..self.dimentions.0{
-                    self.tiles[((r-line_counter)*self.dimentions.0+c) as usize]=self.tiles[(r*self.dimentions.0+c) as usize];
-                }
-            }
-        }
-        return line_counter as u32
-    }
-    fn update_drop_loc(&mut self) -> isize{
-        let mut i = 0;
-        while self.move_down(){
-            i+=1;
-        }
-        self.drop_loc=self.falling_loc;
-        self.falling_loc+=i*self.dimentions.0;
-        return self.drop_loc
+}
+
+/// Name of the cookie holding the best Ultra scores for games of `seconds`.
+fn ultra_best_cookie(seconds: u32) -> String{
+    format!("ultra_best_{}", seconds)
+}
+
+/// Name of the cookie holding the longest times survived in Survival games starting at `seconds`.
+fn survival_best_cookie(seconds: u32) -> String{
+    format!("survival_best_{}", seconds)
+}
+
+/// A titled list of best times, or nothing if there are none yet.
+fn view_times(title: &str, times: &[u64]) -> Html{
+    if times.is_empty(){
+        return html!{}
     }
-    fn rotate_clockwise(&mut self){
-        self.falling_rot=(self.falling_rot+1)%4;
-        if !self.check_in_bounds() || self.check_overlap(){
-            for (x,y) in self.falling_piece.secondary_tests(self.falling_rot){
-                let d = x+y*self.dimentions.0;
-                self.falling_loc+=d;
-                if self.check_in_bounds() && !self.check_overlap(){
-                    self.update_drop_loc();
-                    return
+    html!{
+        <div class="highscore-section">
+        <h1>{title}</h1>
+        {
+            times.iter().map(|t| {
+                html!{
+                    <div class="highscore-list-item">
+                        {format_time(*t)}
+                    </div>
                 }
-                self.falling_loc-=d;
-            }
-            self.falling_rot=(self.falling_rot+3)%4;
-        }
-        self.update_drop_loc();
-    }
-    fn check_in_bounds(&self) -> bool{
-        let horiz_extents = self.falling_piece.horiz_extents(self.falling_rot);
-        return (self.falling_loc+2*self.dimentions.0+horiz_extents.0)%self.dimentions.0<=self.dimentions.0-horiz_extents.1+horiz_extents.0 &&
-        self.falling_piece.vert_extents(self.falling_rot).0+self.row()>=0
-    }
-    fn row(&self) -> isize{
-        return if self.falling_loc+self.falling_piece.horiz_extents(self.falling_rot).0<0 {(self.falling_loc+1-self.dimentions.0)/self.dimentions.0} else {self.falling_loc/self.dimentions.0}
-    }
-    fn check_overlap(&self) -> bool{
-        for i in self.falling_piece.get_idx_arr(self.falling_rot){
-            let loc: isize = self.falling_loc+i/4*self.dimentions.0+i%4;
-            if loc>=0 && loc<self.dimentions.0*self.dimentions.1 && self.tiles[loc as usize].is_some(){
-                return true
-            }
+            }).collect::<Html>()
         }
-        return false 
+        </div>
     }
-    fn drop(&mut self) -> u32{
-        let mut cells_dropped = 0;
-        while self.move_down(){cells_dropped+=1};
-        for i in self.falling_piece.get_idx_arr(self.falling_rot){
-            let loc: isize = self.falling_loc+i/4*self.dimentions.0+i%4;
-            if loc>=0 && loc<self.dimentions.0*self.dimentions.1{
-                self.tiles[loc as usize]=Some(self.falling_piece);
+}
+
+/// How long each Master section took, from the game times they were finished at.
+fn section_durations(times: &[u64]) -> Vec<u64>{
+    times.iter().enumerate().map(|(i, t)| t-if i==0 {0} else {times[i-1]}).collect()
+}
+
+/// Levels covered by Master section `i`, like "100-199".
+fn section_name(i: usize) -> String{
+    let start = i as u32*master::SECTION_LEVELS;
+    format!("{:03}-{:03}", start, (start+master::SECTION_LEVELS-1).min(master::MAX_LEVEL))
+}
+
+/// Name of a clear of `lines` rows, with a leading space, for announcements.
+fn clear_name(lines: u32) -> &'static str{
+    [""," Single"," Double"," Triple"," Tetris"][lines.min(4) as usize]
+}
+
+fn view_piece(from: &Option<TetrisPieceType>, rotation: &RotationSystems) -> Html{
+    html!{
+        <div class="piece-display">
+            {
+                (0..4).rev().map(|r|{
+                    html!{
+                        {
+                            (0..4).map(|c| {
+                                html!{
+                                    if from.is_some() && rotation.cells(from.unwrap(), 0).contains(&(c, r)){
+                                        <span class={format!("sidebar-tile filled {}-color",from.unwrap())}/>
+                                    }else{
+                                        <span class="sidebar-tile empty"/>
+                                    }
+                                }
+                            }).collect::<Html>()
+                        }
+                    }
+                }).collect::<Html>()
             }
-        }
-        // self.new_falling_piece();
-        cells_dropped
-    }
-    fn new_falling_piece(&mut self, new_piece: TetrisPieceType) -> bool{
-        self.falling_piece=new_piece;
-        self.falling_loc=193;
-        self.falling_rot=0;
-        if self.check_overlap(){ self.falling_loc+=self.dimentions.0; }
-        !self.check_overlap()
+        </div>
     }
 }
 
-impl TetrisBoard{
-    fn view(&self) -> Html {
-        html!{
-            <div class="board">
-                {
-                    (0..(self.dimentions.1-3)).rev().map(|r|{
-                        html!{
-                            {
-                                (0..self.dimentions.0).map(|c| {
-                                    html!{
-                                        if self.tiles[(c+r*self.dimentions.0) as usize].is_some(){
-                                            <span class={format!("tile filled {}-color",self.tiles[(c+r*self.dimentions.0) as usize].unwrap())}/>
-                                        }else if self.check_loc_for_falling_piece(c+r*self.dimentions.0){
-                                            <span class={format!("tile filled {}-color",self.falling_piece)}/>
-                                        }else if self.check_drop_loc(c+r*self.dimentions.0){
-                                            <span class={format!("tile outline drop-indicator {}-color",self.falling_piece)}/>
-                                        }else{
-                                            <span class="tile empty"/>
-                                        }
+fn view_board(board: &TetrisBoard) -> Html {
+    let width = board.dimentions().0;
+    let tiles = board.tiles();
+    // full rows are only left on the board during the line clear delay
+    let clearing = board.full_rows();
+    html!{
+        <div class="board">
+            {
+                (0..board.visible_height()).rev().map(|r|{
+                    html!{
+                        {
+                            (0..width).map(|c| {
+                                html!{
+                                    if clearing.contains(&r){
+                                        <span class="tile filled clearing"/>
+                                    }else if board.is_garbage(c, r){
+                                        <span class="tile filled garbage-color"/>
+                                    }else if let Some(tile) = tiles[(c+r*width) as usize]{
+                                        <span class={format!("tile filled {}-color",tile)}/>
+                                    }else if board.check_loc_for_falling_piece(c, r){
+                                        <span class={format!("tile filled {}-color",board.falling_piece())}/>
+                                    }else if board.check_drop_loc(c, r){
+                                        <span class={format!("tile outline drop-indicator {}-color",board.falling_piece())}/>
+                                    }else{
+                                        <span class="tile empty"/>
                                     }
-                                }).collect::<Html>()
-                            }
-                        }
-                    }).collect::<Html>()
-                    /*
-                    self.tiles.iter().rev().enumerate().map(|(i,b)|{
-                        html!{
-                            if *b{
-                                <span class="block"/>
-                            }else if self.check_loc_for_falling_piece(i){
-                                <span class="block"/>
-                            }else{
-                                <span class="empty-tile"/>
-                            }
+                                }
+                            }).collect::<Html>()
                         }
-                    }).collect::<Html>()*/
-                }
-            </div>
-        }
+                    }
+                }).collect::<Html>()
+            }
+        </div>
     }
 }